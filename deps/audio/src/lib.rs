@@ -1,8 +1,20 @@
 // mod android_sound;
 // use android_sound as sound;
+mod mixer;
+mod music;
+mod null_output;
+mod output;
+mod pan;
+mod playback;
 mod sound;
+mod sound_group;
 
 use manage::managed;
-pub use sound::Sound;
+pub use mixer::{Bus, Mixer, MixerSettings};
+pub use music::{LoopPoints, Music};
+pub use null_output::{AudioEvent, AudioEventKind, NullOutput};
+pub use playback::Playback;
+pub use sound::{Playable, Sound};
+pub use sound_group::SoundGroup;
 
 managed!(Sound);
@@ -0,0 +1,289 @@
+use std::{
+    sync::{Arc, Condvar, Mutex, MutexGuard, Once},
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use store::{Flush, OnDiskCached};
+
+use crate::playback::Voice;
+
+/// How often fading volumes are updated.
+const FADE_STEP: Duration = Duration::from_millis(15);
+
+/// Group of sounds with shared volume. Volume of every bus is multiplied by
+/// [`Bus::Master`] volume.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Bus {
+    Master,
+    Music,
+    Sfx,
+    Ui,
+}
+
+impl Bus {
+    pub const ALL: [Bus; 4] = [Bus::Master, Bus::Music, Bus::Sfx, Bus::Ui];
+}
+
+/// Volumes of all buses. Can be stored with `store`, see
+/// [`Mixer::load_settings`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MixerSettings {
+    pub master: f32,
+    pub music:  f32,
+    pub sfx:    f32,
+    pub ui:     f32,
+    pub muted:  bool,
+}
+
+impl MixerSettings {
+    const DEFAULT: Self = Self {
+        master: 1.0,
+        music:  1.0,
+        sfx:    1.0,
+        ui:     1.0,
+        muted:  false,
+    };
+
+    pub fn volume(&self, bus: Bus) -> f32 {
+        match bus {
+            Bus::Master => self.master,
+            Bus::Music => self.music,
+            Bus::Sfx => self.sfx,
+            Bus::Ui => self.ui,
+        }
+    }
+
+    fn volume_mut(&mut self, bus: Bus) -> &mut f32 {
+        match bus {
+            Bus::Master => &mut self.master,
+            Bus::Music => &mut self.music,
+            Bus::Sfx => &mut self.sfx,
+            Bus::Ui => &mut self.ui,
+        }
+    }
+
+    /// Volume sounds of `bus` are actually played with.
+    fn output(&self, bus: Bus) -> f32 {
+        if self.muted {
+            return 0.0;
+        }
+
+        match bus {
+            Bus::Master => self.master,
+            bus => self.master * self.volume(bus),
+        }
+    }
+}
+
+impl Default for MixerSettings {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+struct State {
+    settings: MixerSettings,
+    voices:   Vec<Arc<Voice>>,
+    persist:  bool,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    settings: MixerSettings::DEFAULT,
+    voices:   Vec::new(),
+    persist:  false,
+});
+
+static STORED: OnDiskCached<MixerSettings> =
+    OnDiskCached::new("audio_mixer", Flush::After(Duration::from_millis(500)));
+
+static FADES: Condvar = Condvar::new();
+static FADER: Once = Once::new();
+
+fn state() -> MutexGuard<'static, State> {
+    STATE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Volume control of all playing sounds.
+pub struct Mixer;
+
+impl Mixer {
+    pub fn volume(bus: Bus) -> f32 {
+        state().settings.volume(bus)
+    }
+
+    /// `volume` is clamped to `0.0..=1.0`.
+    pub fn set_volume(bus: Bus, volume: f32) {
+        Self::change(|settings| *settings.volume_mut(bus) = volume.clamp(0.0, 1.0));
+    }
+
+    pub fn is_muted() -> bool {
+        state().settings.muted
+    }
+
+    pub fn set_muted(muted: bool) {
+        Self::change(|settings| settings.muted = muted);
+    }
+
+    pub fn settings() -> MixerSettings {
+        state().settings.clone()
+    }
+
+    pub fn apply_settings(settings: MixerSettings) {
+        Self::change(|current| *current = settings);
+    }
+
+    /// Restores settings saved by previous run and saves every following
+    /// change.
+    pub fn load_settings() {
+        let settings = STORED.get();
+        let mut state = state();
+        state.persist = true;
+        state.settings = settings;
+        state.update_voices();
+    }
+
+    /// Stops all sounds of `bus`. [`Bus::Master`] stops everything.
+    pub fn stop(bus: Bus) {
+        for voice in &state().voices {
+            if bus == Bus::Master || voice.bus() == bus {
+                voice.stop();
+            }
+        }
+    }
+
+    /// Settings are stored after the state is unlocked because store calls
+    /// change subscribers right away and they may use the mixer.
+    fn change(change: impl FnOnce(&mut MixerSettings)) {
+        let mut state = state();
+        change(&mut state.settings);
+        state.update_voices();
+
+        let stored = state.persist.then(|| state.settings.clone());
+        drop(state);
+
+        if let Some(settings) = stored {
+            STORED.set(settings);
+        }
+    }
+
+    pub(crate) fn output_volume(bus: Bus) -> f32 {
+        state().settings.output(bus)
+    }
+
+    pub(crate) fn add_voice(voice: Arc<Voice>) {
+        let mut state = state();
+        voice.update_volume(state.settings.output(voice.bus()));
+        state.voices.retain(|voice| !voice.is_finished());
+        state.voices.push(voice);
+    }
+
+    /// Starts fader thread if needed and wakes it up.
+    pub(crate) fn fade_started() {
+        FADER.call_once(|| {
+            thread::Builder::new()
+                .name("audio fader".into())
+                .spawn(fade_loop)
+                .expect("Failed to spawn audio fader thread");
+        });
+        // Fader checks fades with state locked so taking the lock makes sure
+        // it is either waiting or will see the new fade.
+        drop(state());
+        FADES.notify_one();
+    }
+}
+
+impl State {
+    fn update_voices(&self) {
+        for voice in &self.voices {
+            voice.update_volume(self.settings.output(voice.bus()));
+        }
+    }
+}
+
+fn fade_loop() {
+    let mut state = state();
+
+    loop {
+        state.voices.retain(|voice| !voice.is_finished());
+
+        if !state.voices.iter().any(|voice| voice.is_fading()) {
+            state = FADES.wait(state).unwrap_or_else(|poisoned| poisoned.into_inner());
+            continue;
+        }
+
+        for voice in &state.voices {
+            voice.step_fade();
+            voice.update_volume(state.settings.output(voice.bus()));
+        }
+
+        drop(state);
+        thread::sleep(FADE_STEP);
+        state = self::state();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{
+        null_output::test::{assert_level, constant_sound, lock_audio, wait_for},
+        AudioEventKind, Bus, Mixer, NullOutput,
+    };
+
+    #[test]
+    fn bus_volumes() {
+        let _lock = lock_audio();
+        let sound = constant_sound(0.5, Duration::from_secs(10));
+
+        Mixer::set_volume(Bus::Master, 0.5);
+        Mixer::set_volume(Bus::Sfx, 0.4);
+        Mixer::set_volume(Bus::Music, 0.0);
+        assert_eq!(Mixer::volume(Bus::Sfx), 0.4);
+
+        let playback = sound.play_on(Bus::Sfx, 0.8);
+        assert_eq!(playback.volume(), 0.8);
+        assert_level(Duration::from_millis(20), 0.5 * 0.5 * 0.4 * 0.8);
+
+        Mixer::set_volume(Bus::Sfx, 2.0);
+        assert_eq!(Mixer::volume(Bus::Sfx), 1.0);
+        assert_level(Duration::from_millis(20), 0.5 * 0.5 * 0.8);
+
+        playback.set_volume(0.5);
+        assert_level(Duration::from_millis(20), 0.5 * 0.5 * 0.5);
+
+        Mixer::set_muted(true);
+        assert!(Mixer::is_muted());
+        assert_level(Duration::from_millis(20), 0.0);
+        Mixer::set_muted(false);
+
+        Mixer::stop(Bus::Music);
+        assert!(!playback.is_finished());
+        Mixer::stop(Bus::Master);
+        assert!(playback.is_finished());
+        assert_eq!(NullOutput::events().last().unwrap().kind, AudioEventKind::Stop);
+    }
+
+    #[test]
+    fn fades() {
+        let _lock = lock_audio();
+        let sound = constant_sound(0.5, Duration::from_secs(10));
+
+        let playback = sound.play_on(Bus::Ui, 1.0);
+        playback.fade_to(0.2, Duration::from_millis(50));
+        assert_eq!(playback.volume(), 1.0);
+
+        wait_for(|| playback.volume() == 0.2);
+        assert_level(Duration::from_millis(20), 0.5 * 0.2);
+
+        playback.fade_out(Duration::from_millis(50));
+        assert!(!playback.is_finished());
+
+        wait_for(|| playback.is_finished());
+        assert_eq!(playback.volume(), 0.0);
+        assert_level(Duration::from_millis(20), 0.0);
+    }
+}
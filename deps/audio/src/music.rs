@@ -0,0 +1,359 @@
+use std::{
+    fmt::{Debug, Formatter},
+    fs::File,
+    io::{self, BufReader, Cursor, Read, Seek},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use log::error;
+use manage::{managed, resource_loader::ResourceLoader};
+use rodio::{Decoder, Source};
+
+use crate::{sound::DEFAULT_SOUND_DATA, Bus, Playback};
+
+trait Input: Read + Seek + Send + Sync {}
+
+impl<T: Read + Seek + Send + Sync> Input for T {}
+
+/// Music is streamed from disk. Only music loaded from memory keeps its data.
+#[derive(Clone)]
+enum Data {
+    File(PathBuf),
+    Memory(Arc<[u8]>),
+}
+
+impl Data {
+    fn open(&self) -> io::Result<Decoder<Box<dyn Input>>> {
+        let input: Box<dyn Input> = match self {
+            Data::File(path) => Box::new(BufReader::new(File::open(path)?)),
+            Data::Memory(data) => Box::new(Cursor::new(data.clone())),
+        };
+        Decoder::new(input).map_err(io::Error::other)
+    }
+}
+
+/// Part of the track that is repeated. Everything before `start` plays once.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct LoopPoints {
+    pub start: Duration,
+    /// End of the track if `None`.
+    pub end:   Option<Duration>,
+}
+
+/// Music that is currently playing on [`Bus::Music`].
+struct Current {
+    path:     PathBuf,
+    playback: Playback,
+}
+
+static CURRENT: Mutex<Option<Current>> = Mutex::new(None);
+
+fn current() -> MutexGuard<'static, Option<Current>> {
+    CURRENT.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Long looping track played on [`Bus::Music`]. Unlike [`crate::Sound`] it
+/// is decoded while playing so only a small part of it is in memory. Only
+/// one track is current at a time, starting another one replaces it.
+pub struct Music {
+    path:        PathBuf,
+    data:        Data,
+    loop_points: LoopPoints,
+}
+
+impl Music {
+    pub fn loop_points(&self) -> LoopPoints {
+        self.loop_points
+    }
+
+    /// Applies to playbacks started after this call. Loop points are not
+    /// changed if `end` is not after `start`.
+    pub fn set_loop_points(&mut self, start: Duration, end: Option<Duration>) {
+        if end.is_some_and(|end| end <= start) {
+            error!(
+                "Invalid loop points of {}: end {end:?} is not after start {start:?}",
+                self.path.display()
+            );
+            return;
+        }
+
+        self.loop_points = LoopPoints { start, end };
+    }
+
+    /// Stops current music and starts this track.
+    pub fn play(&self) -> Playback {
+        Self::stop_current(Duration::ZERO);
+        let playback = self.start(1.0);
+        self.make_current(&playback);
+        playback
+    }
+
+    /// Fades current music out while this track fades in. Does nothing if
+    /// this track is already playing, so it can be called on every level
+    /// change.
+    pub fn crossfade(&self, duration: Duration) -> Playback {
+        let mut current = current();
+
+        if let Some(current) = current.as_ref() {
+            if current.path == self.path && !current.playback.is_finished() {
+                current.playback.fade_to(1.0, duration);
+                return current.playback.clone();
+            }
+        }
+
+        if let Some(previous) = current.take() {
+            previous.playback.fade_out(duration);
+        }
+        drop(current);
+
+        let playback = self.start(0.0);
+        playback.fade_to(1.0, duration);
+        self.make_current(&playback);
+        playback
+    }
+
+    pub fn current() -> Option<Playback> {
+        current().as_ref().map(|current| current.playback.clone())
+    }
+
+    /// Stops current music. Zero `fade` stops it immediately.
+    pub fn stop_current(fade: Duration) {
+        let Some(current) = current().take() else {
+            return;
+        };
+
+        if fade.is_zero() {
+            current.playback.stop();
+        } else {
+            current.playback.fade_out(fade);
+        }
+    }
+
+    fn start(&self, volume: f32) -> Playback {
+        let source = Looped::new(self.data.clone(), self.loop_points)
+            .map_err(|err| error!("Failed to play music {}: {err}", self.path.display()))
+            .ok();
+
+        let name = self.path.file_name().unwrap_or(self.path.as_os_str());
+        Playback::start(source, &name.to_string_lossy(), Bus::Music, volume)
+    }
+
+    fn make_current(&self, playback: &Playback) {
+        *current() = Some(Current {
+            path:     self.path.clone(),
+            playback: playback.clone(),
+        });
+    }
+}
+
+/// Plays the track once and then repeats the loop part forever.
+struct Looped {
+    data:        Data,
+    decoder:     Decoder<Box<dyn Input>>,
+    channels:    u16,
+    sample_rate: u32,
+    /// Samples read since the beginning of the track.
+    position:    u64,
+    loop_start:  u64,
+    loop_end:    Option<u64>,
+}
+
+impl Looped {
+    fn new(data: Data, loop_points: LoopPoints) -> io::Result<Self> {
+        let decoder = data.open()?;
+        let channels = decoder.channels();
+        let sample_rate = decoder.sample_rate();
+
+        let samples = |time: Duration| {
+            (time.as_secs_f64() * f64::from(sample_rate)).round() as u64 * u64::from(channels)
+        };
+
+        Ok(Self {
+            data,
+            decoder,
+            channels,
+            sample_rate,
+            position: 0,
+            loop_start: samples(loop_points.start),
+            loop_end: loop_points.end.map(samples),
+        })
+    }
+
+    /// Seeks to loop start. Decoders that can't seek are reopened and skip
+    /// samples before loop start.
+    fn restart(&mut self) -> io::Result<()> {
+        let start = Duration::from_secs_f64(
+            (self.loop_start / u64::from(self.channels)) as f64 / f64::from(self.sample_rate),
+        );
+
+        if self.decoder.try_seek(start).is_err() {
+            self.decoder = self.data.open()?;
+            for _ in 0..self.loop_start {
+                self.decoder.next();
+            }
+        }
+
+        self.position = self.loop_start;
+        Ok(())
+    }
+}
+
+impl Iterator for Looped {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        let at_loop_end = self.loop_end.is_some_and(|end| self.position >= end);
+
+        let sample = match (at_loop_end, self.decoder.next()) {
+            (false, Some(sample)) => sample,
+            _ => {
+                if let Err(err) = self.restart() {
+                    error!("Failed to loop music: {err}");
+                    return None;
+                }
+                // Track has nothing after loop start.
+                self.decoder.next()?
+            }
+        };
+
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl Source for Looped {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl ResourceLoader for Music {
+    fn load_path(path: &Path) -> Self {
+        if !path.is_file() {
+            error!(
+                "Music file not found: {}. Returning default sound",
+                path.display()
+            );
+            return Self::load_data(DEFAULT_SOUND_DATA, path.display());
+        }
+
+        Self {
+            path:        path.into(),
+            data:        Data::File(path.into()),
+            loop_points: LoopPoints::default(),
+        }
+    }
+
+    fn load_data(data: &[u8], name: impl ToString) -> Self {
+        Self {
+            path:        name.to_string().into(),
+            data:        Data::Memory(data.into()),
+            loop_points: LoopPoints::default(),
+        }
+    }
+}
+
+impl Debug for Music {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.path.fmt(f)
+    }
+}
+
+managed!(Music);
+
+#[cfg(test)]
+mod test {
+    use std::{io::Cursor, time::Duration};
+
+    use hound::{SampleFormat, WavSpec, WavWriter};
+    use manage::resource_loader::ResourceLoader;
+
+    use crate::{
+        music::{Data, Looped},
+        LoopPoints, Music,
+    };
+
+    /// Mono track at 1000 Hz where each sample is its index.
+    fn counting_track(len: i16) -> Data {
+        let spec = WavSpec {
+            channels:        1,
+            sample_rate:     1000,
+            bits_per_sample: 16,
+            sample_format:   SampleFormat::Int,
+        };
+
+        let mut data = Cursor::new(vec![]);
+        let mut writer = WavWriter::new(&mut data, spec).unwrap();
+        for sample in 0..len {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        Data::Memory(data.into_inner().into())
+    }
+
+    fn play(loop_points: LoopPoints, samples: usize) -> Vec<i16> {
+        Looped::new(counting_track(8), loop_points).unwrap().take(samples).collect()
+    }
+
+    #[test]
+    fn loop_points() {
+        assert_eq!(
+            play(LoopPoints::default(), 20),
+            [0, 1, 2, 3, 4, 5, 6, 7, 0, 1, 2, 3, 4, 5, 6, 7, 0, 1, 2, 3]
+        );
+
+        assert_eq!(
+            play(
+                LoopPoints {
+                    start: Duration::from_millis(2),
+                    end:   None,
+                },
+                16
+            ),
+            [0, 1, 2, 3, 4, 5, 6, 7, 2, 3, 4, 5, 6, 7, 2, 3]
+        );
+
+        assert_eq!(
+            play(
+                LoopPoints {
+                    start: Duration::from_millis(2),
+                    end:   Some(Duration::from_millis(5)),
+                },
+                14
+            ),
+            [0, 1, 2, 3, 4, 2, 3, 4, 2, 3, 4, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn invalid_loop_points() {
+        let mut music = Music::load_data(&[], "invalid_loop_points");
+        music.set_loop_points(Duration::from_secs(1), Some(Duration::from_secs(2)));
+
+        music.set_loop_points(Duration::from_secs(3), Some(Duration::from_secs(3)));
+        music.set_loop_points(Duration::from_secs(3), Some(Duration::from_secs(1)));
+
+        assert_eq!(
+            music.loop_points(),
+            LoopPoints {
+                start: Duration::from_secs(1),
+                end:   Some(Duration::from_secs(2)),
+            }
+        );
+    }
+}
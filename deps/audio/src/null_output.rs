@@ -0,0 +1,373 @@
+use std::{
+    io, mem,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use gm::Clock;
+use hound::{SampleFormat, WavSpec, WavWriter};
+use rodio::{
+    dynamic_mixer::{mixer, DynamicMixer, DynamicMixerController},
+    Sink,
+};
+
+use crate::Bus;
+
+const CHANNELS: u16 = 2;
+const SAMPLE_RATE: u32 = 44_100;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AudioEventKind {
+    Play,
+    Stop,
+}
+
+/// Sound played or stopped while [`NullOutput`] is enabled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioEvent {
+    pub kind:   AudioEventKind,
+    /// File name of the sound.
+    pub sound:  String,
+    pub bus:    Bus,
+    /// Volume passed to `play`. Bus volumes are not applied.
+    pub volume: f32,
+    /// [`Clock`] time since [`NullOutput::enable`].
+    pub time:   Duration,
+}
+
+struct State {
+    started:    Duration,
+    /// Clock time sounds were played up to. `None` after samples were rendered
+    /// explicitly.
+    drained:    Option<Duration>,
+    events:     Vec<AudioEvent>,
+    controller: Arc<DynamicMixerController<f32>>,
+    mixer:      DynamicMixer<f32>,
+}
+
+impl State {
+    /// Plays sounds up to current clock time unless samples are rendered
+    /// explicitly.
+    fn drain(&mut self) {
+        let Some(drained) = self.drained else {
+            return;
+        };
+
+        let now = Clock::elapsed();
+        self.mix(samples(now) - samples(drained), drop);
+        self.drained = Some(now);
+    }
+
+    /// Passes up to `samples` mixed samples to `output`. Mixer ends when
+    /// nothing is playing. It is replaced then so sounds played later start
+    /// on a whole frame.
+    fn mix(&mut self, samples: usize, mut output: impl FnMut(f32)) {
+        for _ in 0..samples {
+            let Some(sample) = self.mixer.next() else {
+                (self.controller, self.mixer) = mixer(CHANNELS, SAMPLE_RATE);
+                return;
+            };
+            output(sample);
+        }
+    }
+}
+
+/// Number of interleaved samples played in `duration`.
+fn samples(duration: Duration) -> usize {
+    (duration.as_secs_f64() * f64::from(SAMPLE_RATE)).round() as usize * usize::from(CHANNELS)
+}
+
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+fn state() -> MutexGuard<'static, Option<State>> {
+    STATE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Output that doesn't need an audio device. Records played sounds so tests
+/// can check them. Sounds advance with [`Clock`] so they finish when they
+/// would on a device. After the first [`NullOutput::render`] or
+/// [`NullOutput::advance`] sounds advance only with these calls so captured
+/// audio is the same on every run.
+pub struct NullOutput;
+
+impl NullOutput {
+    /// Sounds played after this call go to null output instead of the
+    /// device. Calling again clears recorded events and pending samples.
+    pub fn enable() {
+        let (controller, mixer) = mixer(CHANNELS, SAMPLE_RATE);
+        let now = Clock::elapsed();
+        *state() = Some(State {
+            started: now,
+            drained: Some(now),
+            events: vec![],
+            controller,
+            mixer,
+        });
+    }
+
+    pub fn is_enabled() -> bool {
+        state().is_some()
+    }
+
+    pub fn events() -> Vec<AudioEvent> {
+        state().as_ref().map(|state| state.events.clone()).unwrap_or_default()
+    }
+
+    pub fn take_events() -> Vec<AudioEvent> {
+        state().as_mut().map(|state| mem::take(&mut state.events)).unwrap_or_default()
+    }
+
+    /// Whether sound with `name` was played since [`NullOutput::enable`] or
+    /// last [`NullOutput::take_events`].
+    pub fn played(name: &str) -> bool {
+        state().as_ref().is_some_and(|state| {
+            state
+                .events
+                .iter()
+                .any(|event| event.kind == AudioEventKind::Play && event.sound == name)
+        })
+    }
+
+    /// Mixes `duration` of all playing sounds into interleaved stereo samples
+    /// at 44100 Hz. Volumes of buses and playbacks are applied.
+    pub fn render(duration: Duration) -> Vec<f32> {
+        let samples = samples(duration);
+        let mut output = Vec::with_capacity(samples);
+
+        if let Some(state) = state().as_mut() {
+            state.drained = None;
+            state.mix(samples, |sample| output.push(sample));
+        }
+
+        output.resize(samples, 0.0);
+        output
+    }
+
+    /// Plays sounds for `duration` without keeping the samples.
+    pub fn advance(duration: Duration) {
+        if let Some(state) = state().as_mut() {
+            state.drained = None;
+            state.mix(samples(duration), drop);
+        }
+    }
+
+    /// Same as [`NullOutput::render`] but writes 16 bit WAV file.
+    pub fn render_wav(duration: Duration, path: impl AsRef<Path>) -> io::Result<()> {
+        let spec = WavSpec {
+            channels:        CHANNELS,
+            sample_rate:     SAMPLE_RATE,
+            bits_per_sample: 16,
+            sample_format:   SampleFormat::Int,
+        };
+
+        let mut writer = WavWriter::create(path, spec).map_err(io::Error::other)?;
+
+        for sample in Self::render(duration) {
+            let sample = (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+            writer.write_sample(sample).map_err(io::Error::other)?;
+        }
+
+        writer.finalize().map_err(io::Error::other)
+    }
+
+    /// Plays sounds up to current clock time. Called when playback state is
+    /// checked.
+    pub(crate) fn update() {
+        if let Some(state) = state().as_mut() {
+            state.drain();
+        }
+    }
+
+    pub(crate) fn sink() -> Option<Sink> {
+        let mut state = state();
+        let state = state.as_mut()?;
+        state.drain();
+        let (sink, output) = Sink::new_idle();
+        state.controller.add(output);
+        Some(sink)
+    }
+
+    pub(crate) fn record(kind: AudioEventKind, sound: &str, bus: Bus, volume: f32) {
+        if let Some(state) = state().as_mut() {
+            let time = Clock::elapsed().saturating_sub(state.started);
+            state.events.push(AudioEvent {
+                kind,
+                sound: sound.into(),
+                bus,
+                volume,
+                time,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::{
+        io::Cursor,
+        sync::{Mutex, MutexGuard},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use gm::Clock;
+    use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+    use manage::resource_loader::ResourceLoader;
+
+    use crate::{AudioEvent, AudioEventKind, Bus, Mixer, MixerSettings, NullOutput, Sound};
+
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    /// Null output and mixer are global so tests that play sounds can't run in
+    /// parallel. Starts with clean null output and default mixer settings.
+    pub(crate) fn lock_audio() -> MutexGuard<'static, ()> {
+        let lock = LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Mixer::stop(Bus::Master);
+        NullOutput::enable();
+        Mixer::apply_settings(MixerSettings::default());
+        lock
+    }
+
+    /// Mono WAV file with every sample set to `level`.
+    pub(crate) fn wav(level: f32, duration: Duration) -> Vec<u8> {
+        let spec = WavSpec {
+            channels:        1,
+            sample_rate:     super::SAMPLE_RATE,
+            bits_per_sample: 16,
+            sample_format:   SampleFormat::Int,
+        };
+
+        let mut data = Cursor::new(vec![]);
+        let mut writer = WavWriter::new(&mut data, spec).unwrap();
+        let sample = (level * 32768.0) as i16;
+
+        for _ in 0..(duration.as_secs_f64() * f64::from(super::SAMPLE_RATE)).round() as usize {
+            writer.write_sample(sample).unwrap();
+        }
+
+        writer.finalize().unwrap();
+        data.into_inner()
+    }
+
+    pub(crate) fn constant_sound(level: f32, duration: Duration) -> Sound {
+        Sound::load_data(&wav(level, duration), "constant.wav")
+    }
+
+    /// Fades and fallback output run on their own threads in real time.
+    /// Waits until `condition` is true instead of sleeping fixed time.
+    #[track_caller]
+    pub(crate) fn wait_for(condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(Instant::now() < deadline, "Condition not met in time");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Renders `duration` and checks level of both channels at its end.
+    /// Volume changes are applied to playing sounds every 5 ms so the
+    /// beginning is not checked.
+    #[track_caller]
+    pub(crate) fn assert_level(duration: Duration, expected: f32) {
+        let samples = NullOutput::render(duration);
+        let end = &samples[samples.len() - 2..];
+        assert!(
+            end.iter().all(|sample| (sample - expected).abs() < 0.001),
+            "Expected level {expected}, got {end:?}"
+        );
+    }
+
+    #[test]
+    fn events() {
+        let _lock = lock_audio();
+        Clock::set_manual(true);
+        NullOutput::enable();
+
+        let sound = constant_sound(0.5, Duration::from_secs(1));
+
+        Clock::advance(Duration::from_millis(100));
+        let playback = sound.play_on(Bus::Ui, 0.5);
+        Clock::advance(Duration::from_millis(100));
+        playback.stop();
+        playback.stop();
+
+        Clock::set_manual(false);
+
+        let event = |kind, time| AudioEvent {
+            kind,
+            sound: "constant.wav".into(),
+            bus: Bus::Ui,
+            volume: 0.5,
+            time: Duration::from_millis(time),
+        };
+
+        assert_eq!(
+            NullOutput::events(),
+            [event(AudioEventKind::Play, 100), event(AudioEventKind::Stop, 200)]
+        );
+
+        assert!(NullOutput::played("constant.wav"));
+        assert!(!NullOutput::played("other.wav"));
+
+        assert_eq!(NullOutput::take_events().len(), 2);
+        assert!(NullOutput::events().is_empty());
+        assert!(!NullOutput::played("constant.wav"));
+    }
+
+    #[test]
+    fn render() {
+        let _lock = lock_audio();
+
+        constant_sound(0.5, Duration::from_millis(100)).play_on(Bus::Sfx, 0.5);
+        constant_sound(0.25, Duration::from_millis(50)).play_on(Bus::Sfx, 1.0);
+
+        let samples = NullOutput::render(Duration::from_millis(200));
+        assert_eq!(samples.len(), 44_100 / 5 * 2);
+
+        // Both sounds, then the longer one and then silence. Sounds start with
+        // a short delay of the output queue.
+        assert!((samples[3000] - 0.5).abs() < 0.001);
+        assert!((samples[3001] - 0.5).abs() < 0.001);
+        assert!((samples[7000] - 0.25).abs() < 0.001);
+        assert!(samples[12_000..].iter().all(|sample| *sample == 0.0));
+
+        let path = std::env::temp_dir().join("null_output_test.wav");
+        constant_sound(0.5, Duration::from_secs(1)).play_on(Bus::Sfx, 1.0);
+        NullOutput::render_wav(Duration::from_millis(100), &path).unwrap();
+
+        let mut reader = WavReader::open(&path).unwrap();
+        assert_eq!(reader.spec().channels, 2);
+        assert_eq!(reader.spec().sample_rate, 44_100);
+        assert_eq!(reader.len(), 4410 * 2);
+
+        let samples: Vec<i16> = reader.samples().map(Result::unwrap).collect();
+        assert!(samples[1000..].iter().all(|sample| (sample - i16::MAX / 2).abs() <= 1));
+    }
+
+    #[test]
+    fn advance_with_clock() {
+        let _lock = lock_audio();
+        Clock::set_manual(true);
+
+        let playback = constant_sound(0.5, Duration::from_millis(50)).play();
+        assert!(!playback.is_finished());
+
+        Clock::advance(Duration::from_millis(40));
+        assert!(!playback.is_finished());
+
+        Clock::advance(Duration::from_millis(60));
+        assert!(playback.is_finished());
+
+        // After explicit render sounds don't advance with clock.
+        let playback = constant_sound(0.5, Duration::from_millis(50)).play();
+        NullOutput::advance(Duration::from_millis(10));
+        Clock::advance(Duration::from_millis(100));
+        assert!(!playback.is_finished());
+
+        NullOutput::advance(Duration::from_millis(100));
+        assert!(playback.is_finished());
+
+        Clock::set_manual(false);
+    }
+}
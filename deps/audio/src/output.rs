@@ -0,0 +1,113 @@
+use std::{
+    sync::{mpsc, Arc, OnceLock},
+    thread,
+    time::Duration,
+};
+
+use log::{error, warn};
+use rodio::{
+    dynamic_mixer::{mixer, DynamicMixerController},
+    OutputStream, OutputStreamHandle, Sink,
+};
+
+use crate::NullOutput;
+
+const SILENT_CHANNELS: u16 = 2;
+const SILENT_SAMPLE_RATE: u32 = 44_100;
+/// Silent output consumes samples in chunks of this length.
+const SILENT_STEP: Duration = Duration::from_millis(10);
+
+static DEVICE: OnceLock<Option<OutputStreamHandle>> = OnceLock::new();
+static SILENT: OnceLock<Arc<DynamicMixerController<f32>>> = OnceLock::new();
+
+/// Sink on the default output device. Device is opened once on first call.
+/// Sounds are played silently if there is no device so the game works the
+/// same on machines without audio. [`NullOutput`] takes precedence when
+/// enabled.
+pub(crate) fn sink() -> Sink {
+    if let Some(sink) = NullOutput::sink() {
+        return sink;
+    }
+
+    let Some(device) = DEVICE.get_or_init(open_device) else {
+        return silent_sink();
+    };
+
+    Sink::try_new(device).unwrap_or_else(|err| {
+        error!("Failed to play on audio device: {err}. Playing silently.");
+        silent_sink()
+    })
+}
+
+/// `OutputStream` can't be moved between threads so it is kept by a thread
+/// that lives as long as the app.
+fn open_device() -> Option<OutputStreamHandle> {
+    let (sender, receiver) = mpsc::channel();
+
+    let spawned =
+        thread::Builder::new()
+            .name("audio output".into())
+            .spawn(move || match OutputStream::try_default() {
+                Ok((_stream, handle)) => {
+                    _ = sender.send(Some(handle));
+                    loop {
+                        thread::park();
+                    }
+                }
+                Err(err) => {
+                    warn!("Failed to open audio device: {err}. Sounds will be silent.");
+                    _ = sender.send(None);
+                }
+            });
+
+    if let Err(err) = spawned {
+        error!("Failed to spawn audio output thread: {err}. Sounds will be silent.");
+        return None;
+    }
+
+    receiver.recv().ok().flatten()
+}
+
+fn silent_sink() -> Sink {
+    let (sink, output) = Sink::new_idle();
+    SILENT.get_or_init(start_silent_output).add(output);
+    sink
+}
+
+/// Consumes samples in real time without playing them so sounds finish when
+/// they would on a device.
+fn start_silent_output() -> Arc<DynamicMixerController<f32>> {
+    let (controller, mut mixer) = mixer(SILENT_CHANNELS, SILENT_SAMPLE_RATE);
+
+    let samples_per_step =
+        (SILENT_SAMPLE_RATE as f32 * SILENT_STEP.as_secs_f32()) as usize * SILENT_CHANNELS as usize;
+
+    thread::Builder::new()
+        .name("silent audio output".into())
+        .spawn(move || loop {
+            mixer.by_ref().take(samples_per_step).for_each(drop);
+            thread::sleep(SILENT_STEP);
+        })
+        .expect("Failed to spawn silent audio output thread");
+
+    controller
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use rodio::{source::SineWave, Source};
+
+    use crate::{null_output::test::wait_for, output::silent_sink};
+
+    #[test]
+    fn silent_output() {
+        let sink = silent_sink();
+        sink.append(SineWave::new(440.0).take_duration(Duration::from_millis(50)));
+        assert!(!sink.empty());
+
+        // Samples are consumed in real time without a device.
+        wait_for(|| sink.empty());
+    }
+}
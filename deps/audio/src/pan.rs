@@ -0,0 +1,218 @@
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use rodio::{source::SeekError, Sample, Source};
+
+/// Stereo position shared between [`crate::Playback`] and the playing
+/// source. `-1.0` is left, `1.0` is right.
+#[derive(Default)]
+pub(crate) struct Pan(AtomicU32);
+
+impl Pan {
+    pub(crate) fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn set(&self, pan: f32) {
+        self.0.store(pan.clamp(-1.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    /// Centered sound keeps full volume in both channels.
+    fn gains(&self) -> [f32; 2] {
+        let pan = self.get();
+        [(1.0 - pan).min(1.0), (1.0 + pan).min(1.0)]
+    }
+}
+
+/// Applies [`Pan`] to a source. Mono sources are played as stereo. Sources
+/// with more than two channels are not panned.
+pub(crate) struct Panned<S> {
+    source:  S,
+    pan:     Arc<Pan>,
+    mono:    bool,
+    gains:   [f32; 2],
+    channel: u16,
+    /// Right channel of the last mono sample.
+    right:   Option<i16>,
+}
+
+impl<S: Source<Item = i16>> Panned<S> {
+    pub(crate) fn new(source: S, pan: Arc<Pan>) -> Self {
+        Self {
+            mono: source.channels() == 1,
+            source,
+            pan,
+            gains: [1.0; 2],
+            channel: 0,
+            right: None,
+        }
+    }
+}
+
+impl<S: Source<Item = i16>> Iterator for Panned<S> {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if let Some(right) = self.right.take() {
+            return Some(right);
+        }
+
+        let sample = self.source.next()?;
+
+        if self.mono {
+            let [left, right] = self.pan.gains();
+            self.right = Some(sample.amplify(right));
+            return Some(sample.amplify(left));
+        }
+
+        if self.source.channels() != 2 {
+            return Some(sample);
+        }
+
+        // Gains are updated once per frame so both channels use the same pan.
+        if self.channel == 0 {
+            self.gains = self.pan.gains();
+        }
+        let gain = self.gains[usize::from(self.channel)];
+        self.channel = 1 - self.channel;
+
+        Some(sample.amplify(gain))
+    }
+}
+
+impl<S: Source<Item = i16>> Source for Panned<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        let len = self.source.current_frame_len()?;
+        Some(if self.mono {
+            len * 2 + usize::from(self.right.is_some())
+        } else {
+            len
+        })
+    }
+
+    fn channels(&self) -> u16 {
+        if self.mono {
+            2
+        } else {
+            self.source.channels()
+        }
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.source.try_seek(pos)?;
+        self.channel = 0;
+        self.right = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Duration, vec::IntoIter};
+
+    use rodio::Source;
+
+    use crate::pan::{Pan, Panned};
+
+    /// Source that is a single frame.
+    struct Frame {
+        channels: u16,
+        samples:  IntoIter<i16>,
+    }
+
+    impl Frame {
+        fn new(channels: u16, samples: Vec<i16>) -> Self {
+            Self {
+                channels,
+                samples: samples.into_iter(),
+            }
+        }
+    }
+
+    impl Iterator for Frame {
+        type Item = i16;
+
+        fn next(&mut self) -> Option<i16> {
+            self.samples.next()
+        }
+    }
+
+    impl Source for Frame {
+        fn current_frame_len(&self) -> Option<usize> {
+            Some(self.samples.len())
+        }
+
+        fn channels(&self) -> u16 {
+            self.channels
+        }
+
+        fn sample_rate(&self) -> u32 {
+            44_100
+        }
+
+        fn total_duration(&self) -> Option<Duration> {
+            None
+        }
+    }
+
+    fn pan(pan: f32) -> Arc<Pan> {
+        let result = Arc::new(Pan::default());
+        result.set(pan);
+        result
+    }
+
+    #[test]
+    fn gains() {
+        assert_eq!(pan(0.0).gains(), [1.0, 1.0]);
+        assert_eq!(pan(-1.0).gains(), [1.0, 0.0]);
+        assert_eq!(pan(1.0).gains(), [0.0, 1.0]);
+        assert_eq!(pan(0.5).gains(), [0.5, 1.0]);
+        assert_eq!(pan(-0.25).gains(), [1.0, 0.75]);
+        assert_eq!(pan(5.0).gains(), [0.0, 1.0]);
+    }
+
+    #[test]
+    fn mono_to_stereo() {
+        let source = Frame::new(1, vec![1000, 2000, 3000]);
+        let mut panned = Panned::new(source, pan(0.5));
+
+        assert_eq!(panned.channels(), 2);
+        assert_eq!(panned.current_frame_len(), Some(6));
+
+        assert_eq!(panned.next(), Some(500));
+        assert_eq!(panned.current_frame_len(), Some(5));
+        assert_eq!(panned.next(), Some(1000));
+        assert_eq!(panned.current_frame_len(), Some(4));
+
+        assert_eq!(panned.collect::<Vec<_>>(), [1000, 2000, 1500, 3000]);
+    }
+
+    #[test]
+    fn stereo() {
+        let source = Frame::new(2, vec![1000, 1000, 2000, 2000]);
+        let pan = pan(-0.5);
+        let mut panned = Panned::new(source, pan.clone());
+
+        assert_eq!(panned.channels(), 2);
+        assert_eq!(panned.current_frame_len(), Some(4));
+        assert_eq!(panned.next(), Some(1000));
+
+        // Pan changed in the middle of a frame applies to the next frame.
+        pan.set(0.5);
+        assert_eq!(panned.next(), Some(500));
+        assert_eq!(panned.collect::<Vec<_>>(), [1000, 2000]);
+    }
+}
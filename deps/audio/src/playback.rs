@@ -0,0 +1,238 @@
+use std::{
+    fmt::{Debug, Formatter},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
+
+use rodio::{Sink, Source};
+
+use crate::{
+    null_output::AudioEventKind,
+    output::sink,
+    pan::{Pan, Panned},
+    Bus, Mixer, NullOutput,
+};
+
+struct Fade {
+    from:     f32,
+    to:       f32,
+    start:    Instant,
+    duration: Duration,
+    /// Stop the sound when fade is finished.
+    stop:     bool,
+}
+
+struct Volume {
+    volume:      f32,
+    /// Set by positional sounds. Not affected by fades.
+    attenuation: f32,
+    fade:        Option<Fade>,
+}
+
+/// Single playing sound. Kept alive by [`Mixer`] until it finishes.
+pub(crate) struct Voice {
+    sink:    Sink,
+    name:    String,
+    bus:     Bus,
+    volume:  Mutex<Volume>,
+    pan:     Arc<Pan>,
+    stopped: AtomicBool,
+}
+
+impl Voice {
+    pub(crate) fn new(sink: Sink, name: String, bus: Bus, volume: f32, pan: Arc<Pan>) -> Self {
+        Self {
+            sink,
+            name,
+            bus,
+            volume: Mutex::new(Volume {
+                volume,
+                attenuation: 1.0,
+                fade: None,
+            }),
+            pan,
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn bus(&self) -> Bus {
+        self.bus
+    }
+
+    pub(crate) fn stop(&self) {
+        if self.stopped.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        self.sink.stop();
+        NullOutput::record(AudioEventKind::Stop, &self.name, self.bus, self.volume().volume);
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        NullOutput::update();
+        self.stopped.load(Ordering::Relaxed) || self.sink.empty()
+    }
+
+    pub(crate) fn is_fading(&self) -> bool {
+        self.volume().fade.is_some()
+    }
+
+    /// `output` is volume of the bus this voice plays on.
+    pub(crate) fn update_volume(&self, output: f32) {
+        let volume = self.volume();
+        self.sink.set_volume(volume.volume * volume.attenuation * output);
+    }
+
+    pub(crate) fn step_fade(&self) {
+        let mut volume = self.volume();
+
+        let Some(fade) = &volume.fade else {
+            return;
+        };
+
+        let progress = if fade.duration.is_zero() {
+            1.0
+        } else {
+            (fade.start.elapsed().as_secs_f32() / fade.duration.as_secs_f32()).min(1.0)
+        };
+
+        let stop = fade.stop;
+
+        if progress < 1.0 {
+            volume.volume = fade.from + (fade.to - fade.from) * progress;
+        } else {
+            // Ends exactly at target volume.
+            volume.volume = fade.to;
+            volume.fade = None;
+            if stop {
+                drop(volume);
+                self.stop();
+            }
+        }
+    }
+
+    fn volume(&self) -> MutexGuard<Volume> {
+        self.volume.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Handle of a playing sound. Sound keeps playing when handle is dropped.
+#[derive(Clone)]
+pub struct Playback {
+    voice: Arc<Voice>,
+}
+
+impl Playback {
+    /// Sound is silent if `source` is `None`. It still gets a playback so
+    /// callers don't have to handle decoding errors.
+    pub(crate) fn start(
+        source: Option<impl Source<Item = i16> + Send + 'static>,
+        name: &str,
+        bus: Bus,
+        volume: f32,
+    ) -> Self {
+        let sink = sink();
+        let pan = Arc::new(Pan::default());
+
+        if let Some(source) = source {
+            sink.append(Panned::new(source, pan.clone()));
+        }
+
+        NullOutput::record(AudioEventKind::Play, name, bus, volume);
+        let voice = Arc::new(Voice::new(sink, name.into(), bus, volume, pan));
+        Mixer::add_voice(voice.clone());
+        Self { voice }
+    }
+
+    pub fn bus(&self) -> Bus {
+        self.voice.bus
+    }
+
+    pub fn stop(&self) {
+        self.voice.stop();
+    }
+
+    pub fn pause(&self) {
+        self.voice.sink.pause();
+    }
+
+    pub fn resume(&self) {
+        self.voice.sink.play();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.voice.sink.is_paused()
+    }
+
+    /// Sound reached its end or was stopped.
+    pub fn is_finished(&self) -> bool {
+        self.voice.is_finished()
+    }
+
+    /// Volume of this sound before bus volumes are applied.
+    pub fn volume(&self) -> f32 {
+        self.voice.volume().volume
+    }
+
+    /// Cancels fade in progress.
+    pub fn set_volume(&self, volume: f32) {
+        let mut state = self.voice.volume();
+        state.volume = volume.max(0.0);
+        state.fade = None;
+        drop(state);
+        self.voice.update_volume(Mixer::output_volume(self.voice.bus));
+    }
+
+    /// `-1.0` is left, `1.0` is right. Sounds with more than two channels
+    /// can't be panned.
+    pub fn pan(&self) -> f32 {
+        self.voice.pan.get()
+    }
+
+    pub fn set_pan(&self, pan: f32) {
+        self.voice.pan.set(pan);
+    }
+
+    /// Additional volume multiplier used for sounds that come from some
+    /// place. Kept separately from [`Playback::volume`] so fades still work.
+    pub fn set_attenuation(&self, attenuation: f32) {
+        self.voice.volume().attenuation = attenuation.clamp(0.0, 1.0);
+        self.voice.update_volume(Mixer::output_volume(self.voice.bus));
+    }
+
+    /// Gradually changes volume from current value.
+    pub fn fade_to(&self, volume: f32, duration: Duration) {
+        self.fade(volume.max(0.0), duration, false);
+    }
+
+    /// Gradually silences the sound and stops it.
+    pub fn fade_out(&self, duration: Duration) {
+        self.fade(0.0, duration, true);
+    }
+
+    fn fade(&self, to: f32, duration: Duration, stop: bool) {
+        let mut state = self.voice.volume();
+        state.fade = Some(Fade {
+            from: state.volume,
+            to,
+            start: Instant::now(),
+            duration,
+            stop,
+        });
+        drop(state);
+        Mixer::fade_started();
+    }
+}
+
+impl Debug for Playback {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Playback")
+            .field("sound", &self.voice.name)
+            .field("bus", &self.voice.bus)
+            .field("volume", &self.volume())
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
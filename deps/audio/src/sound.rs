@@ -5,33 +5,70 @@ use std::{
     fs::read,
     io::Cursor,
     path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
 };
 
 use log::error;
 use manage::resource_loader::ResourceLoader;
-use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink};
+use refs::Weak;
+use rodio::{Decoder, Source};
+
+use crate::{Bus, Playback};
+
+/// Anything that can be played with default settings. Lets sprites and
+/// levels play both [`Sound`] and [`crate::SoundGroup`].
+pub trait Playable {
+    fn play(&self) -> Playback;
+}
 
 pub struct Sound {
-    path:          PathBuf,
-    _stream:       OutputStream,
-    stream_handle: OutputStreamHandle,
-    data:          Vec<u8>,
+    path:    PathBuf,
+    /// Decoded when sound is loaded so playing doesn't have to decode it
+    /// again. `None` if data could not be decoded.
+    samples: Option<Samples>,
 }
 
 impl Sound {
-    pub fn play(&mut self) {
-        let cursor = Cursor::new(self.data.clone());
-        let input = Decoder::new(cursor).unwrap();
+    /// Volume of [`Sound::play`]. Same loudness sounds had before buses were
+    /// added.
+    pub const DEFAULT_VOLUME: f32 = 0.1;
+
+    /// Plays on [`Bus::Sfx`] with [`Sound::DEFAULT_VOLUME`].
+    pub fn play(&self) -> Playback {
+        self.play_on(Bus::Sfx, Self::DEFAULT_VOLUME)
+    }
+
+    /// `volume` is multiplied by `bus` and master volumes.
+    pub fn play_on(&self, bus: Bus, volume: f32) -> Playback {
+        self.play_with_speed(bus, volume, 1.0)
+    }
+
+    /// `speed` changes pitch and duration of the sound.
+    pub(crate) fn play_with_speed(&self, bus: Bus, volume: f32, speed: f32) -> Playback {
+        let source = self.samples.clone().map(|samples| Buffered::new(samples).speed(speed));
+        let name = self.path.file_name().unwrap_or(self.path.as_os_str());
+        Playback::start(source, &name.to_string_lossy(), bus, volume)
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.samples.as_ref().map(Samples::duration).unwrap_or_default()
+    }
+}
 
-        let sink = Sink::try_new(&self.stream_handle).unwrap();
+impl Playable for Sound {
+    fn play(&self) -> Playback {
+        Sound::play(self)
+    }
+}
 
-        sink.set_volume(0.1);
-        sink.append(input);
-        sink.detach();
+impl<T: Playable> Playable for Weak<T> {
+    fn play(&self) -> Playback {
+        (**self).play()
     }
 }
 
-static DEFAULT_SOUND_DATA: &[u8] = include_bytes!("pek.wav");
+pub(crate) static DEFAULT_SOUND_DATA: &[u8] = include_bytes!("pek.wav");
 
 impl ResourceLoader for Sound {
     fn load_path(path: &Path) -> Self {
@@ -50,14 +87,14 @@ impl ResourceLoader for Sound {
     }
 
     fn load_data(data: &[u8], name: impl ToString) -> Self {
-        let (stream, stream_handle) = OutputStream::try_default().unwrap();
+        let path: PathBuf = name.to_string().into();
 
-        Self {
-            path: name.to_string().into(),
-            _stream: stream,
-            stream_handle,
-            data: data.into(),
-        }
+        let samples = Decoder::new(Cursor::new(data.to_vec()))
+            .map(Samples::decode)
+            .map_err(|err| error!("Failed to decode sound {}: {err}", path.display()))
+            .ok();
+
+        Self { path, samples }
     }
 }
 
@@ -66,3 +103,65 @@ impl Debug for Sound {
         self.path.fmt(f)
     }
 }
+
+/// Decoded interleaved samples shared by all playbacks of a sound.
+#[derive(Clone)]
+struct Samples {
+    channels:    u16,
+    sample_rate: u32,
+    data:        Arc<[i16]>,
+}
+
+impl Samples {
+    fn decode(decoder: Decoder<Cursor<Vec<u8>>>) -> Self {
+        Self {
+            channels:    decoder.channels(),
+            sample_rate: decoder.sample_rate(),
+            data:        decoder.collect(),
+        }
+    }
+
+    fn duration(&self) -> Duration {
+        let frames = self.data.len() / usize::from(self.channels);
+        Duration::from_secs_f64(frames as f64 / f64::from(self.sample_rate))
+    }
+}
+
+struct Buffered {
+    samples:  Samples,
+    position: usize,
+}
+
+impl Buffered {
+    fn new(samples: Samples) -> Self {
+        Self { samples, position: 0 }
+    }
+}
+
+impl Iterator for Buffered {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        let sample = self.samples.data.get(self.position).copied()?;
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl Source for Buffered {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.samples.data.len() - self.position)
+    }
+
+    fn channels(&self) -> u16 {
+        self.samples.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.samples.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(self.samples.duration())
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    fmt::{Debug, Formatter},
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
+
+use rand::{seq::SliceRandom, thread_rng, Rng};
+use refs::Weak;
+use rodio::source::Empty;
+
+use crate::{Bus, Playable, Playback, Sound};
+
+/// Fade applied to a voice stopped to make room for a new one so it doesn't
+/// click.
+const STEAL_FADE: Duration = Duration::from_millis(20);
+
+/// Set of variants of the same sound, e.g. footsteps or shots. Every play
+/// picks a random variant with slightly different pitch and volume so
+/// repeated sounds don't get monotonous.
+pub struct SoundGroup {
+    sounds:        Vec<Weak<Sound>>,
+    bus:           Bus,
+    volume:        f32,
+    volume_jitter: f32,
+    pitch_jitter:  f32,
+    max_voices:    usize,
+    voices:        Mutex<Vec<Playback>>,
+}
+
+impl SoundGroup {
+    /// Plays on [`Bus::Sfx`] without jitter and with up to 4 voices at once.
+    /// Group without sounds plays silence.
+    pub fn new(sounds: impl IntoIterator<Item = Weak<Sound>>) -> Self {
+        Self {
+            sounds:        sounds.into_iter().collect(),
+            bus:           Bus::Sfx,
+            volume:        1.0,
+            volume_jitter: 0.0,
+            pitch_jitter:  0.0,
+            max_voices:    4,
+            voices:        Mutex::default(),
+        }
+    }
+
+    pub fn bus(mut self, bus: Bus) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    pub fn volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Volume of every play is randomly changed by up to `jitter`.
+    pub fn volume_jitter(mut self, jitter: f32) -> Self {
+        self.volume_jitter = jitter.abs();
+        self
+    }
+
+    /// Pitch of every play is randomly changed by up to `jitter`. `0.1` plays
+    /// at 90% to 110% speed.
+    pub fn pitch_jitter(mut self, jitter: f32) -> Self {
+        self.pitch_jitter = jitter.abs().min(0.9);
+        self
+    }
+
+    /// When limit is reached the oldest voice is stopped to play a new one.
+    pub fn max_voices(mut self, max_voices: usize) -> Self {
+        self.max_voices = max_voices.max(1);
+        self
+    }
+
+    /// Sounds of this group that are still playing.
+    pub fn playing(&self) -> usize {
+        let mut voices = self.voices();
+        voices.retain(|voice| !voice.is_finished());
+        voices.len()
+    }
+
+    /// Stops every playing sound of this group.
+    pub fn stop(&self) {
+        for voice in self.voices().drain(..) {
+            voice.stop();
+        }
+    }
+
+    fn voices(&self) -> MutexGuard<Vec<Playback>> {
+        self.voices.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Playable for SoundGroup {
+    fn play(&self) -> Playback {
+        let mut rng = thread_rng();
+
+        let Some(sound) = self.sounds.choose(&mut rng) else {
+            return Playback::start(None::<Empty<i16>>, "empty SoundGroup", self.bus, self.volume);
+        };
+
+        let volume = self.volume + rng.gen_range(-self.volume_jitter..=self.volume_jitter);
+        let speed = 1.0 + rng.gen_range(-self.pitch_jitter..=self.pitch_jitter);
+
+        let mut voices = self.voices();
+        voices.retain(|voice| !voice.is_finished());
+
+        while voices.len() >= self.max_voices {
+            voices.remove(0).fade_out(STEAL_FADE);
+        }
+
+        let playback = sound.play_with_speed(self.bus, volume.max(0.0), speed);
+        voices.push(playback.clone());
+        playback
+    }
+}
+
+impl Debug for SoundGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SoundGroup")
+            .field(
+                "sounds",
+                &self.sounds.iter().map(|sound| &**sound).collect::<Vec<_>>(),
+            )
+            .field("bus", &self.bus)
+            .field("playing", &self.playing())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use refs::Own;
+
+    use crate::{
+        null_output::test::{constant_sound, lock_audio, wait_for},
+        AudioEventKind, NullOutput, Playable, SoundGroup,
+    };
+
+    #[test]
+    fn voice_limit() {
+        let _lock = lock_audio();
+        let sound = Own::new(constant_sound(0.5, Duration::from_secs(10)));
+        let group = SoundGroup::new([sound.weak()]).max_voices(2);
+
+        let first = group.play();
+        let second = group.play();
+        let third = group.play();
+
+        assert_eq!(group.playing(), 2);
+        assert!(!first.is_finished());
+
+        wait_for(|| first.is_finished());
+        assert!(!second.is_finished());
+        assert!(!third.is_finished());
+
+        let kinds: Vec<_> = NullOutput::events().iter().map(|event| event.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                AudioEventKind::Play,
+                AudioEventKind::Play,
+                AudioEventKind::Play,
+                AudioEventKind::Stop
+            ]
+        );
+
+        group.stop();
+        assert_eq!(group.playing(), 0);
+        assert!(second.is_finished());
+    }
+
+    #[test]
+    fn jitter() {
+        let _lock = lock_audio();
+        let sound = Own::new(constant_sound(0.5, Duration::from_millis(10)));
+        let group = SoundGroup::new([sound.weak()])
+            .volume(0.5)
+            .volume_jitter(-0.2)
+            .pitch_jitter(2.0)
+            .max_voices(0);
+
+        assert_eq!(group.pitch_jitter, 0.9);
+        assert_eq!(group.max_voices, 1);
+
+        let volumes: Vec<_> = (0..100).map(|_| group.play().volume()).collect();
+
+        assert!(volumes.iter().all(|volume| (0.3..=0.7).contains(volume)));
+        assert!(volumes.iter().any(|volume| *volume != 0.5));
+    }
+
+    #[test]
+    fn empty() {
+        let _lock = lock_audio();
+        let group = SoundGroup::new([]);
+
+        let playback = group.play();
+
+        assert!(playback.is_finished());
+        assert_eq!(NullOutput::events()[0].sound, "empty SoundGroup");
+    }
+}
@@ -1,107 +1,425 @@
 use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt::{Display, Formatter},
     future::Future,
-    sync::{Arc, Mutex},
-    thread::sleep,
-    time::Duration,
+    panic::Location,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{sync_channel, RecvTimeoutError},
+        Arc, Mutex, MutexGuard, Once,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
 use gm::ToF32;
 use log::warn;
 use refs::is_main_thread;
-use tokio::{
-    spawn,
-    sync::oneshot::{channel, Sender},
-};
+use tokio::{spawn, sync::oneshot::channel, time::sleep};
+
+use crate::{local::poll_local, timer::fire_timers, Timer};
 
 type Callback = Box<dyn FnOnce() + Send>;
-type Callbacks = Mutex<Vec<Callback>>;
-type SignalledCallbacks = Mutex<Vec<(Sender<()>, Callback)>>;
 
-static CALLBACKS: Callbacks = Callbacks::new(vec![]);
-static SIGNALLED: SignalledCallbacks = SignalledCallbacks::new(vec![]);
+struct Queued {
+    action:   Callback,
+    /// Where the callback was dispatched from. Reported when main thread
+    /// doesn't get to it in time.
+    location: &'static Location<'static>,
+    queued:   Instant,
+    reported: bool,
+}
 
-pub async fn from_main<T, A>(action: A) -> T
-where
-    A: FnOnce() -> T + Send + 'static,
-    T: Send + 'static, {
-    assert!(
-        !is_main_thread(),
-        "This is already main thread. Just call it without `from_main`"
-    );
+struct Settings {
+    budget:          Option<Duration>,
+    stall_threshold: Option<Duration>,
+}
 
-    let result = Arc::<Mutex<Option<T>>>::default();
+static QUEUE: Mutex<VecDeque<Queued>> = Mutex::new(VecDeque::new());
 
-    let (sender, receiver) = channel::<()>();
+static SETTINGS: Mutex<Settings> = Mutex::new(Settings {
+    budget:          None,
+    stall_threshold: None,
+});
 
-    let capture = result.clone();
-    SIGNALLED.lock().unwrap().push((
-        sender,
-        Box::new(move || {
-            let mut res = capture.lock().unwrap();
-            *res = action().into();
-        }),
-    ));
+static WATCHDOG: Once = Once::new();
 
-    receiver.await.expect("Failed to receive result in on_main");
+fn queue() -> MutexGuard<'static, VecDeque<Queued>> {
+    QUEUE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
 
-    let res = result.lock().unwrap().take().unwrap();
-    res
+fn settings() -> MutexGuard<'static, Settings> {
+    SETTINGS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn push(location: &'static Location<'static>, action: Callback) {
+    queue().push_back(Queued {
+        action,
+        location,
+        queued: Instant::now(),
+        reported: false,
+    });
 }
 
+/// Reason [`try_on_main_sync`] didn't return a result.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MainThreadError {
+    /// Main thread didn't call the action in time.
+    Timeout {
+        location: &'static Location<'static>,
+        timeout:  Duration,
+    },
+    /// Action panicked on main thread.
+    Panicked { location: &'static Location<'static> },
+}
+
+impl Display for MainThreadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Timeout { location, timeout } => {
+                write!(f, "Main thread didn't call action from {location} in {timeout:?}")
+            }
+            Self::Panicked { location } => {
+                write!(f, "Action from {location} panicked on main thread")
+            }
+        }
+    }
+}
+
+impl Error for MainThreadError {}
+
+#[track_caller]
+pub fn from_main<T, A>(action: A) -> impl Future<Output = T>
+where
+    A: FnOnce() -> T + Send + 'static,
+    T: Send + 'static, {
+    let location = Location::caller();
+
+    async move {
+        assert!(
+            !is_main_thread(),
+            "This is already main thread. Just call it without `from_main`"
+        );
+
+        let (sender, receiver) = channel::<T>();
+        push(location, Box::new(move || _ = sender.send(action())));
+        receiver.await.expect("Failed to receive result in from_main")
+    }
+}
+
+/// Can be awaited on main thread in `spawn_local` futures.
 pub async fn wait_for_next_frame() {
-    from_main(|| {}).await;
+    if is_main_thread() {
+        Timer::wait_frames(1).await;
+    } else {
+        from_main(|| {}).await;
+    }
 }
 
+#[track_caller]
 pub fn on_main(action: impl FnOnce() + Send + 'static) {
     if is_main_thread() {
         action();
     } else {
-        CALLBACKS.lock().unwrap().push(Box::new(action));
+        push(Location::caller(), Box::new(action));
     }
 }
 
-pub fn on_main_sync(action: impl FnOnce() + Send + 'static) {
+/// Blocks until main thread calls `action`. Hangs forever if main thread is
+/// waiting for the caller. Use [`try_on_main_sync`] when that is possible
+/// and [`detect_main_thread_stalls`] to find such places.
+#[track_caller]
+pub fn on_main_sync<T: Send + 'static>(action: impl FnOnce() -> T + Send + 'static) -> T {
     if is_main_thread() {
-        action();
-    } else {
-        let (sender, mut receiver) = channel::<()>();
-        SIGNALLED.lock().unwrap().push((sender, Box::new(action)));
-        while receiver.try_recv().is_err() {}
+        return action();
     }
+
+    let (sender, receiver) = sync_channel(1);
+    push(Location::caller(), Box::new(move || _ = sender.send(action())));
+    receiver.recv().expect("Main thread dropped on_main_sync action")
 }
 
-pub fn after(delay: impl ToF32, action: impl FnOnce() + Send + 'static) {
-    spawn(async move {
-        sleep(Duration::from_secs_f32(delay.to_f32()));
-        CALLBACKS.lock().unwrap().push(Box::new(action));
-    });
+/// Same as [`on_main_sync`] but gives up after `timeout`. Action is not
+/// called if main thread gets to it after timeout. Fails with
+/// [`MainThreadError::Panicked`] if action panics.
+#[track_caller]
+pub fn try_on_main_sync<T: Send + 'static>(
+    timeout: Duration,
+    action: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, MainThreadError> {
+    if is_main_thread() {
+        return Ok(action());
+    }
+
+    let location = Location::caller();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = sync_channel(1);
+
+    let capture = cancelled.clone();
+    push(
+        location,
+        Box::new(move || {
+            if !capture.load(Ordering::Relaxed) {
+                _ = sender.send(action());
+            }
+        }),
+    );
+
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => Ok(result),
+        // Sender is dropped without a result only when action panics.
+        Err(RecvTimeoutError::Disconnected) => Err(MainThreadError::Panicked { location }),
+        Err(RecvTimeoutError::Timeout) => {
+            cancelled.store(true, Ordering::Relaxed);
+            // Action may have finished right before it was cancelled.
+            receiver.try_recv().map_err(|_| MainThreadError::Timeout { location, timeout })
+        }
+    }
+}
+
+/// Calls `action` on main thread after `delay` seconds. See [`Timer`] for
+/// repeating and frame based timers.
+pub fn after(delay: impl ToF32, action: impl FnOnce() + Send + 'static) -> Timer {
+    Timer::after(delay, action)
 }
 
 pub fn async_after(delay: impl ToF32, action: impl Future + Send + 'static) {
     spawn(async move {
-        sleep(Duration::from_secs_f32(delay.to_f32()));
+        sleep(Duration::from_secs_f32(delay.to_f32())).await;
         action.await;
     });
 }
 
+/// Limits time `invoke_dispatched` spends on callbacks from other threads in
+/// one frame. Callbacks that don't fit are called next frame. At least one
+/// callback is called every frame. `None` removes the limit.
+pub fn set_dispatch_budget(budget: Option<Duration>) {
+    settings().budget = budget;
+}
+
+/// How often disabled watchdog checks if stall detection is enabled again.
+const WATCHDOG_IDLE: Duration = Duration::from_millis(100);
+
+/// Logs a warning with caller location of callbacks that wait for main
+/// thread longer than `threshold`. Helps to find `on_main_sync` deadlocks
+/// and long frames.
+pub fn detect_main_thread_stalls(threshold: Duration) {
+    settings().stall_threshold = Some(threshold);
+
+    WATCHDOG.call_once(|| {
+        thread::Builder::new()
+            .name("main thread watchdog".into())
+            .spawn(watchdog)
+            .expect("Failed to spawn main thread watchdog");
+    });
+}
+
+fn watchdog() {
+    loop {
+        // Keeps running while disabled so detection can be enabled again.
+        let Some(threshold) = settings().stall_threshold else {
+            thread::sleep(WATCHDOG_IDLE);
+            continue;
+        };
+
+        thread::sleep((threshold / 2).max(Duration::from_millis(10)));
+
+        let mut queue = queue();
+        let pending = queue.len();
+
+        let Some(oldest) = queue.front_mut() else {
+            continue;
+        };
+
+        let waiting = oldest.queued.elapsed();
+
+        if waiting >= threshold && !oldest.reported {
+            oldest.reported = true;
+            warn!(
+                "Main thread stalled: callback from {} is waiting for {waiting:?}. {pending} callbacks \
+                 pending",
+                oldest.location
+            );
+        }
+    }
+}
+
 pub fn invoke_dispatched() {
-    let Ok(mut callback) = CALLBACKS.try_lock() else {
-        warn!("Failed to lock CALLBACKS");
-        return;
-    };
+    let start = Instant::now();
+    let budget = settings().budget;
 
-    for action in callback.drain(..) {
-        action();
+    // Callbacks dispatched while these are called wait for the next frame.
+    let count = queue().len();
+
+    for index in 0..count {
+        if index > 0 && budget.is_some_and(|budget| start.elapsed() >= budget) {
+            break;
+        }
+
+        let Some(queued) = queue().pop_front() else {
+            break;
+        };
+
+        (queued.action)();
     }
-    drop(callback);
 
-    let Ok(mut signalled) = SIGNALLED.try_lock() else {
-        warn!("Failed to lock SIGNALLED");
-        return;
+    fire_timers();
+    poll_local();
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::{
+        panic::catch_unwind,
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc,
+        },
+        thread,
+        time::{Duration, Instant},
     };
 
-    for (signal, action) in signalled.drain(..) {
-        action();
-        signal.send(()).unwrap();
+    use refs::set_current_thread_as_main;
+
+    use crate::{
+        detect_main_thread_stalls,
+        dispatch::{queue, settings, WATCHDOG_IDLE},
+        invoke_dispatched, on_main, set_dispatch_budget,
+        timer::test::lock_timers,
+        try_on_main_sync, MainThreadError,
+    };
+
+    /// Same as [`lock_timers`] and makes this thread main with an empty queue.
+    pub(crate) fn lock_main() -> impl Drop {
+        let lock = lock_timers();
+        set_current_thread_as_main();
+        queue().clear();
+        lock
+    }
+
+    #[test]
+    fn timeout() {
+        let _lock = lock_main();
+        let called = Arc::new(AtomicU32::new(0));
+        let capture = called.clone();
+
+        let result = thread::spawn(move || {
+            try_on_main_sync(Duration::from_millis(20), move || {
+                capture.fetch_add(1, Ordering::Relaxed);
+            })
+        })
+        .join()
+        .unwrap();
+
+        assert!(matches!(
+            result,
+            Err(MainThreadError::Timeout { timeout, .. }) if timeout == Duration::from_millis(20)
+        ));
+
+        // Main thread got to it too late.
+        invoke_dispatched();
+        assert_eq!(called.load(Ordering::Relaxed), 0);
+        assert!(queue().is_empty());
+    }
+
+    #[test]
+    fn result() {
+        let _lock = lock_main();
+
+        let caller = thread::spawn(|| try_on_main_sync(Duration::from_secs(10), || 5));
+
+        while !caller.is_finished() {
+            invoke_dispatched();
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(caller.join().unwrap(), Ok(5));
+    }
+
+    #[test]
+    fn panicked() {
+        let _lock = lock_main();
+
+        let caller = thread::spawn(|| {
+            try_on_main_sync(Duration::from_secs(10), || -> u32 { panic!("action failed") })
+        });
+
+        while !caller.is_finished() {
+            _ = catch_unwind(invoke_dispatched);
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert!(matches!(
+            caller.join().unwrap(),
+            Err(MainThreadError::Panicked { .. })
+        ));
+    }
+
+    #[test]
+    fn budget() {
+        let _lock = lock_main();
+        let called = Arc::new(AtomicU32::new(0));
+
+        let capture = called.clone();
+        thread::spawn(move || {
+            for _ in 0..3 {
+                let capture = capture.clone();
+                on_main(move || {
+                    capture.fetch_add(1, Ordering::Relaxed);
+                    thread::sleep(Duration::from_millis(5));
+                });
+            }
+        })
+        .join()
+        .unwrap();
+
+        set_dispatch_budget(Some(Duration::from_millis(1)));
+
+        // At least one callback is called every frame, the rest wait.
+        invoke_dispatched();
+        assert_eq!(called.load(Ordering::Relaxed), 1);
+        assert_eq!(queue().len(), 2);
+
+        invoke_dispatched();
+        assert_eq!(called.load(Ordering::Relaxed), 2);
+
+        set_dispatch_budget(None);
+
+        invoke_dispatched();
+        assert_eq!(called.load(Ordering::Relaxed), 3);
+        assert!(queue().is_empty());
+    }
+
+    #[test]
+    fn watchdog() {
+        let _lock = lock_main();
+
+        thread::spawn(|| on_main(|| {})).join().unwrap();
+        detect_main_thread_stalls(Duration::from_millis(20));
+        wait_reported();
+
+        settings().stall_threshold = None;
+        invoke_dispatched();
+
+        // Gives watchdog time to see that detection is disabled.
+        thread::spawn(|| on_main(|| {})).join().unwrap();
+        thread::sleep(WATCHDOG_IDLE);
+        assert!(!queue().front().unwrap().reported);
+
+        detect_main_thread_stalls(Duration::from_millis(20));
+        wait_reported();
+
+        settings().stall_threshold = None;
+        invoke_dispatched();
+    }
+
+    /// Watchdog runs in real time so the test waits for it with a timeout.
+    fn wait_reported() {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !queue().front().unwrap().reported {
+            assert!(Instant::now() < deadline, "Stall was not reported");
+            thread::sleep(Duration::from_millis(1));
+        }
     }
 }
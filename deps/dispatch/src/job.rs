@@ -0,0 +1,269 @@
+use std::{
+    fmt::{Debug, Formatter},
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+};
+
+use log::error;
+
+use crate::on_main;
+
+type Work = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct State {
+    /// `f32` bits.
+    progress:  AtomicU32,
+    cancelled: AtomicBool,
+    finished:  AtomicBool,
+}
+
+static POOL: OnceLock<Mutex<Sender<Work>>> = OnceLock::new();
+
+/// Workers leave one core to main thread.
+fn start_pool() -> Mutex<Sender<Work>> {
+    let (sender, receiver) = channel::<Work>();
+    let receiver = Arc::new(Mutex::new(receiver));
+
+    let workers = thread::available_parallelism().map_or(2, |cores| cores.get().saturating_sub(1).max(1));
+
+    for index in 0..workers {
+        let receiver = receiver.clone();
+        thread::Builder::new()
+            .name(format!("job worker {index}"))
+            .spawn(move || worker(&receiver))
+            .expect("Failed to spawn job worker");
+    }
+
+    Mutex::new(sender)
+}
+
+fn worker(receiver: &Mutex<Receiver<Work>>) {
+    loop {
+        let work = receiver.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).recv();
+
+        let Ok(work) = work else {
+            return;
+        };
+
+        work();
+    }
+}
+
+fn submit(work: Work) {
+    POOL.get_or_init(start_pool)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .send(work)
+        .expect("Job pool is stopped");
+}
+
+/// Passed to job work to report progress and check for cancellation.
+pub struct JobProgress {
+    state: Arc<State>,
+}
+
+impl JobProgress {
+    /// `0.0..=1.0`. Values out of range are clamped.
+    pub fn set(&self, progress: f32) {
+        self.state.progress.store(progress.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    /// Long jobs should check it and return early. Result of a cancelled job
+    /// is dropped anyway.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Handle of CPU heavy work running on the job pool. Dropping the handle
+/// doesn't cancel the job.
+#[derive(Clone)]
+pub struct Job {
+    state: Arc<State>,
+}
+
+impl Job {
+    /// Runs `work` on a pool worker and calls `completion` with its result on
+    /// main thread. `completion` is not called if job is cancelled or `work`
+    /// panics.
+    pub fn spawn<T: Send + 'static>(
+        work: impl FnOnce(&JobProgress) -> T + Send + 'static,
+        completion: impl FnOnce(T) + Send + 'static,
+    ) -> Self {
+        let state = Arc::new(State::default());
+        let progress = JobProgress { state: state.clone() };
+
+        submit(Box::new(move || {
+            if progress.is_cancelled() {
+                progress.state.finished.store(true, Ordering::Relaxed);
+                return;
+            }
+
+            let Ok(result) = catch_unwind(AssertUnwindSafe(|| work(&progress))) else {
+                error!("Job panicked");
+                progress.state.finished.store(true, Ordering::Relaxed);
+                return;
+            };
+
+            progress.set(1.0);
+
+            on_main(move || {
+                if !progress.is_cancelled() {
+                    completion(result);
+                }
+                progress.state.finished.store(true, Ordering::Relaxed);
+            });
+        }));
+
+        Self { state }
+    }
+
+    /// Last value reported by the job. `1.0` after it returns.
+    pub fn progress(&self) -> f32 {
+        f32::from_bits(self.state.progress.load(Ordering::Relaxed))
+    }
+
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// `true` after completion was called or job was cancelled or panicked.
+    pub fn is_finished(&self) -> bool {
+        self.state.finished.load(Ordering::Relaxed) || self.is_cancelled()
+    }
+}
+
+impl Debug for Job {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Job")
+            .field("progress", &self.progress())
+            .field("cancelled", &self.is_cancelled())
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc,
+        },
+        thread::sleep,
+        time::Duration,
+    };
+
+    use crate::{dispatch::test::lock_main, invoke_dispatched, Job};
+
+    /// Calls dispatched callbacks like the frame loop until job has finished
+    /// on both worker and main thread.
+    fn wait(job: &Job) {
+        while !job.state.finished.load(Ordering::Relaxed) {
+            invoke_dispatched();
+            sleep(Duration::from_millis(1));
+        }
+        invoke_dispatched();
+    }
+
+    #[test]
+    fn completion() {
+        let _lock = lock_main();
+        let result = Arc::new(AtomicU32::new(0));
+        let capture = result.clone();
+
+        let job = Job::spawn(
+            |progress| {
+                progress.set(0.5);
+                progress.set(5.0);
+                40 + 2
+            },
+            move |value| capture.store(value, Ordering::Relaxed),
+        );
+
+        wait(&job);
+
+        assert!(job.is_finished());
+        assert!(!job.is_cancelled());
+        assert_eq!(job.progress(), 1.0);
+        assert_eq!(result.load(Ordering::Relaxed), 42);
+    }
+
+    #[test]
+    fn progress() {
+        let _lock = lock_main();
+
+        let job = Job::spawn(
+            |progress| {
+                progress.set(0.25);
+                while !progress.is_cancelled() {
+                    sleep(Duration::from_millis(1));
+                }
+            },
+            |()| {},
+        );
+
+        while job.progress() == 0.0 {
+            sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(job.progress(), 0.25);
+        assert!(!job.is_finished());
+
+        job.cancel();
+        wait(&job);
+    }
+
+    #[test]
+    fn cancellation() {
+        let _lock = lock_main();
+        let completed = Arc::new(AtomicU32::new(0));
+        let capture = completed.clone();
+
+        let job = Job::spawn(
+            |progress| {
+                while !progress.is_cancelled() {
+                    sleep(Duration::from_millis(1));
+                }
+            },
+            move |()| _ = capture.fetch_add(1, Ordering::Relaxed),
+        );
+
+        sleep(Duration::from_millis(10));
+        job.cancel();
+
+        assert!(job.is_cancelled());
+        assert!(job.is_finished());
+
+        wait(&job);
+        assert_eq!(completed.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn panic() {
+        let _lock = lock_main();
+        let completed = Arc::new(AtomicU32::new(0));
+        let capture = completed.clone();
+
+        let job = Job::spawn(
+            |_| -> u32 { panic!("Job failed") },
+            move |_| _ = capture.fetch_add(1, Ordering::Relaxed),
+        );
+
+        wait(&job);
+
+        assert!(job.is_finished());
+        assert!(!job.is_cancelled());
+        assert_eq!(completed.load(Ordering::Relaxed), 0);
+    }
+}
@@ -4,5 +4,8 @@
 extern crate core;
 
 mod dispatch;
+mod job;
+mod local;
+mod timer;
 
-pub use crate::dispatch::*;
+pub use crate::{dispatch::*, job::*, local::*, timer::*};
@@ -0,0 +1,116 @@
+use std::{
+    cell::RefCell,
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Wake, Waker},
+};
+
+use refs::assert_main_thread;
+
+struct Task {
+    future: Pin<Box<dyn Future<Output = ()>>>,
+    waker:  Arc<TaskWaker>,
+}
+
+#[derive(Default)]
+struct TaskWaker {
+    woken: AtomicBool,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::Relaxed);
+    }
+}
+
+thread_local! {
+    static TASKS: RefCell<Vec<Task>> = const { RefCell::new(vec![]) };
+}
+
+/// Runs `future` on main thread. It doesn't have to be `Send` so it can
+/// hold views and other main thread state across `await`s. Woken futures are
+/// polled once per frame by `invoke_dispatched`.
+pub fn spawn_local(future: impl Future<Output = ()> + 'static) {
+    assert_main_thread();
+
+    let waker = Arc::new(TaskWaker::default());
+    waker.woken.store(true, Ordering::Relaxed);
+
+    TASKS.with_borrow_mut(|tasks| {
+        tasks.push(Task {
+            future: Box::pin(future),
+            waker,
+        });
+    });
+}
+
+pub(crate) fn poll_local() {
+    // Tasks spawned while polling are added after and polled next frame.
+    let tasks = TASKS.with_borrow_mut(mem::take);
+    let mut pending = Vec::with_capacity(tasks.len());
+
+    for mut task in tasks {
+        if !task.waker.woken.swap(false, Ordering::Relaxed) {
+            pending.push(task);
+            continue;
+        }
+
+        let waker = Waker::from(task.waker.clone());
+
+        if task.future.as_mut().poll(&mut Context::from_waker(&waker)).is_pending() {
+            pending.push(task);
+        }
+    }
+
+    TASKS.with_borrow_mut(|tasks| {
+        pending.append(tasks);
+        *tasks = pending;
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use gm::Clock;
+    use refs::set_current_thread_as_main;
+
+    use crate::{invoke_dispatched, spawn_local, timer::test::lock_timers, Timer};
+
+    #[test]
+    fn wait_frames() {
+        let _lock = lock_timers();
+        set_current_thread_as_main();
+
+        let start = Clock::frame();
+        let resumed = Rc::new(RefCell::new(vec![]));
+        let capture = resumed.clone();
+
+        spawn_local(async move {
+            capture.borrow_mut().push(Clock::frame());
+            Timer::wait_frames(2).await;
+            capture.borrow_mut().push(Clock::frame());
+            Timer::wait_frames(1).await;
+            capture.borrow_mut().push(Clock::frame());
+        });
+
+        // Spawned future is polled on the next dispatch.
+        assert!(resumed.borrow().is_empty());
+
+        for _ in 0..5 {
+            invoke_dispatched();
+            Clock::next_frame();
+        }
+
+        assert_eq!(*resumed.borrow(), [start, start + 2, start + 3]);
+    }
+}
@@ -0,0 +1,393 @@
+use std::{
+    fmt::{Debug, Formatter},
+    mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    time::Duration,
+};
+
+use gm::{Clock, ToF32};
+use refs::Weak;
+use tokio::sync::oneshot::channel;
+
+type Action = Box<dyn FnMut() + Send>;
+type IsFreed = Box<dyn Fn() -> bool + Send>;
+
+#[derive(Copy, Clone)]
+enum Delay {
+    Time(Duration),
+    Frames(u64),
+}
+
+#[derive(Copy, Clone)]
+enum Due {
+    /// [`Clock::elapsed`] value.
+    Time(Duration),
+    Frame(u64),
+}
+
+/// State shared by a timer handle and its entry. Owner is set here and not in
+/// the entry because the entry is taken out of the list while it fires.
+#[derive(Default)]
+struct Shared {
+    active: AtomicBool,
+    owner:  Mutex<Option<IsFreed>>,
+}
+
+impl Shared {
+    fn is_cancelled(&self) -> bool {
+        let owner = self.owner.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if owner.as_ref().is_some_and(|is_freed| is_freed()) {
+            self.active.store(false, Ordering::Relaxed);
+        }
+        !self.active.load(Ordering::Relaxed)
+    }
+}
+
+struct Entry {
+    delay:  Delay,
+    due:    Due,
+    repeat: bool,
+    shared: Arc<Shared>,
+    action: Action,
+}
+
+impl Entry {
+    fn is_due(&self, now: Duration, frame: u64) -> bool {
+        match self.due {
+            Due::Time(due) => due <= now,
+            Due::Frame(due) => due <= frame,
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.shared.is_cancelled()
+    }
+}
+
+static TIMERS: Mutex<Vec<Entry>> = Mutex::new(vec![]);
+
+fn timers() -> MutexGuard<'static, Vec<Entry>> {
+    TIMERS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn due(delay: Delay) -> Due {
+    match delay {
+        Delay::Time(duration) => Due::Time(Clock::elapsed() + duration),
+        Delay::Frames(frames) => Due::Frame(Clock::frame() + frames.max(1)),
+    }
+}
+
+/// Handle of a scheduled action. Actions are called on main thread.
+/// Dropping the handle doesn't cancel the timer.
+#[derive(Clone)]
+pub struct Timer {
+    shared: Arc<Shared>,
+}
+
+impl Timer {
+    /// Calls `action` once after `delay` seconds.
+    pub fn after(delay: impl ToF32, action: impl FnOnce() + Send + 'static) -> Self {
+        Self::schedule(Delay::Time(seconds(delay)), false, once(action))
+    }
+
+    /// Calls `action` every `interval` seconds until cancelled.
+    pub fn every(interval: impl ToF32, action: impl FnMut() + Send + 'static) -> Self {
+        Self::schedule(Delay::Time(seconds(interval)), true, Box::new(action))
+    }
+
+    /// Calls `action` once after `frames` frames. `0` is the same as `1`.
+    /// Frames are counted by [`Clock::frame`].
+    pub fn after_frames(frames: u32, action: impl FnOnce() + Send + 'static) -> Self {
+        Self::schedule(Delay::Frames(frames.into()), false, once(action))
+    }
+
+    /// Calls `action` every `frames` frames until cancelled.
+    pub fn every_frames(frames: u32, action: impl FnMut() + Send + 'static) -> Self {
+        Self::schedule(Delay::Frames(frames.into()), true, Box::new(action))
+    }
+
+    /// Completes after `delay` seconds. Can be awaited in tokio tasks and in
+    /// `spawn_local` futures.
+    pub async fn wait(delay: impl ToF32) {
+        let (sender, receiver) = channel();
+        Self::after(delay, move || _ = sender.send(()));
+        _ = receiver.await;
+    }
+
+    /// Completes after `frames` frames.
+    pub async fn wait_frames(frames: u32) {
+        let (sender, receiver) = channel();
+        Self::after_frames(frames, move || _ = sender.send(()));
+        _ = receiver.await;
+    }
+
+    /// Timer is cancelled when `owner` is freed. Use it when action captures
+    /// a view or a sprite so it doesn't fire after it is gone. Can also be
+    /// called from the timer's own action.
+    pub fn owned_by<T: ?Sized + 'static>(self, owner: Weak<T>) -> Self {
+        *self.shared.owner.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) =
+            Some(Box::new(move || owner.is_null()));
+        self
+    }
+
+    pub fn cancel(&self) {
+        self.shared.active.store(false, Ordering::Relaxed);
+    }
+
+    /// `false` if timer was cancelled or one-shot timer has fired.
+    pub fn is_active(&self) -> bool {
+        self.shared.active.load(Ordering::Relaxed)
+    }
+
+    fn schedule(delay: Delay, repeat: bool, action: Action) -> Self {
+        let shared = Arc::new(Shared {
+            active: AtomicBool::new(true),
+            ..Default::default()
+        });
+
+        timers().push(Entry {
+            delay,
+            due: due(delay),
+            repeat,
+            shared: shared.clone(),
+            action,
+        });
+
+        Self { shared }
+    }
+}
+
+impl Debug for Timer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Timer").field("active", &self.is_active()).finish()
+    }
+}
+
+/// Calls actions of due timers. Called by `invoke_dispatched` every frame.
+pub(crate) fn fire_timers() {
+    let frame = Clock::frame();
+    let now = Clock::elapsed();
+
+    // Actions are called without the lock so they can schedule new timers.
+    let (fired, pending): (Vec<_>, Vec<_>) = mem::take(&mut *timers())
+        .into_iter()
+        .partition(|entry| entry.is_due(now, frame));
+
+    timers().extend(pending.into_iter().filter(|entry| !entry.is_cancelled()));
+
+    for mut entry in fired {
+        if entry.is_cancelled() {
+            continue;
+        }
+
+        (entry.action)();
+
+        if !entry.repeat {
+            entry.shared.active.store(false, Ordering::Relaxed);
+            continue;
+        }
+
+        if entry.is_cancelled() {
+            continue;
+        }
+
+        entry.due = match (entry.due, entry.delay) {
+            // Keeps the pace if frame took longer but doesn't fire twice to catch up.
+            (Due::Time(at), Delay::Time(interval)) => Due::Time((at + interval).max(now)),
+            _ => due(entry.delay),
+        };
+
+        timers().push(entry);
+    }
+}
+
+fn seconds(delay: impl ToF32) -> Duration {
+    Duration::from_secs_f32(delay.to_f32().max(0.0))
+}
+
+fn once(action: impl FnOnce() + Send + 'static) -> Action {
+    let mut action = Some(action);
+    Box::new(move || {
+        if let Some(action) = action.take() {
+            action();
+        }
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::{
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc, Mutex, MutexGuard,
+        },
+        time::Duration,
+    };
+
+    use gm::Clock;
+    use refs::Own;
+
+    use crate::{timer::fire_timers, Timer};
+
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    /// Timers, main queue and clock are global so tests that use them can't run
+    /// in parallel. Clock is manual and already scheduled timers are dropped.
+    pub(crate) fn lock_timers() -> MutexGuard<'static, ()> {
+        let lock = LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Clock::set_manual(true);
+        super::timers().clear();
+        lock
+    }
+
+    fn counter() -> (Arc<AtomicU32>, impl Fn() -> u32) {
+        let count = Arc::new(AtomicU32::new(0));
+        let read = count.clone();
+        (count, move || read.load(Ordering::Relaxed))
+    }
+
+    fn advance(millis: u64) {
+        Clock::advance(Duration::from_millis(millis));
+        fire_timers();
+    }
+
+    fn next_frame() {
+        Clock::next_frame();
+        fire_timers();
+    }
+
+    #[test]
+    fn one_shot() {
+        let _lock = lock_timers();
+        let (count, fired) = counter();
+
+        let timer = Timer::after(0.5, move || _ = count.fetch_add(1, Ordering::Relaxed));
+
+        advance(499);
+        assert_eq!(fired(), 0);
+        assert!(timer.is_active());
+
+        advance(1);
+        assert_eq!(fired(), 1);
+        assert!(!timer.is_active());
+
+        advance(1000);
+        assert_eq!(fired(), 1);
+    }
+
+    #[test]
+    fn repeating() {
+        let _lock = lock_timers();
+        let (count, fired) = counter();
+
+        let timer = Timer::every(0.25, move || _ = count.fetch_add(1, Ordering::Relaxed));
+
+        advance(250);
+        advance(250);
+        assert_eq!(fired(), 2);
+
+        // Long frame doesn't fire twice.
+        advance(600);
+        assert_eq!(fired(), 3);
+
+        advance(250);
+        assert_eq!(fired(), 4);
+
+        timer.cancel();
+        assert!(!timer.is_active());
+
+        advance(500);
+        assert_eq!(fired(), 4);
+    }
+
+    #[test]
+    fn frames() {
+        let _lock = lock_timers();
+        let (once, fired_once) = counter();
+        let (every, fired_every) = counter();
+
+        Timer::after_frames(0, move || _ = once.fetch_add(1, Ordering::Relaxed));
+        let timer = Timer::every_frames(2, move || _ = every.fetch_add(1, Ordering::Relaxed));
+
+        // Time doesn't fire frame timers.
+        advance(1000);
+        assert_eq!(fired_once(), 0);
+
+        next_frame();
+        assert_eq!((fired_once(), fired_every()), (1, 0));
+
+        next_frame();
+        assert_eq!(fired_every(), 1);
+
+        for _ in 0..4 {
+            next_frame();
+        }
+        assert_eq!((fired_once(), fired_every()), (1, 3));
+
+        timer.cancel();
+        next_frame();
+        next_frame();
+        assert_eq!(fired_every(), 3);
+    }
+
+    #[test]
+    fn cancel() {
+        let _lock = lock_timers();
+        let (count, fired) = counter();
+
+        let timer = Timer::after(0.1, move || _ = count.fetch_add(1, Ordering::Relaxed));
+        timer.clone().cancel();
+        assert!(!timer.is_active());
+
+        advance(200);
+        assert_eq!(fired(), 0);
+    }
+
+    #[test]
+    fn owned_by() {
+        let _lock = lock_timers();
+        let (count, fired) = counter();
+
+        let owner = Own::new(5);
+        let timer =
+            Timer::every(0.25, move || _ = count.fetch_add(1, Ordering::Relaxed)).owned_by(owner.weak());
+
+        advance(250);
+        assert_eq!(fired(), 1);
+
+        drop(owner);
+        advance(250);
+        assert_eq!(fired(), 1);
+        assert!(!timer.is_active());
+    }
+
+    #[test]
+    fn owned_by_in_action() {
+        let _lock = lock_timers();
+        let (count, fired) = counter();
+
+        let owner = Own::new(5);
+        let weak = owner.weak();
+        let handle: Arc<Mutex<Option<Timer>>> = Arc::default();
+        let inner = handle.clone();
+
+        let timer = Timer::every(0.25, move || {
+            count.fetch_add(1, Ordering::Relaxed);
+            if let Some(timer) = inner.lock().unwrap().take() {
+                timer.owned_by::<i32>(weak);
+            }
+        });
+        *handle.lock().unwrap() = Some(timer.clone());
+
+        advance(250);
+        assert_eq!(fired(), 1);
+
+        drop(owner);
+        advance(250);
+        assert_eq!(fired(), 1);
+        assert!(!timer.is_active());
+    }
+}
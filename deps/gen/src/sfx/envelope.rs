@@ -0,0 +1,47 @@
+/// Volume of a sound over time. All durations are in seconds.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Envelope {
+    pub attack:  f32,
+    pub sustain: f32,
+    /// Extra volume at the start of sustain that fades out until its end.
+    pub punch:   f32,
+    pub decay:   f32,
+}
+
+impl Envelope {
+    pub fn duration(&self) -> f32 {
+        self.attack + self.sustain + self.decay
+    }
+
+    pub fn amplitude(&self, time: f32) -> f32 {
+        let Envelope {
+            attack,
+            sustain,
+            punch,
+            decay,
+        } = *self;
+
+        if time < 0.0 {
+            0.0
+        } else if time < attack {
+            time / attack
+        } else if time < attack + sustain {
+            1.0 + punch * (1.0 - (time - attack) / sustain)
+        } else if time < attack + sustain + decay {
+            1.0 - (time - attack - sustain) / decay
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Default for Envelope {
+    fn default() -> Self {
+        Self {
+            attack:  0.0,
+            sustain: 0.1,
+            punch:   0.0,
+            decay:   0.2,
+        }
+    }
+}
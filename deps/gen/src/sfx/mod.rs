@@ -0,0 +1,9 @@
+mod envelope;
+mod oscillator;
+mod params;
+mod synth;
+
+pub use envelope::*;
+pub use oscillator::*;
+pub use params::*;
+pub use synth::*;
@@ -0,0 +1,75 @@
+use std::f32::consts::TAU;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Noise changes value this many times per period.
+const NOISE_STEPS: f32 = 32.0;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Waveform {
+    #[default]
+    Square,
+    Saw,
+    Sine,
+    Noise,
+}
+
+/// Produces a wave sample by sample. Frequency and duty can be changed
+/// between samples without clicks because phase is kept.
+#[derive(Debug, Clone)]
+pub struct Oscillator {
+    pub waveform:  Waveform,
+    pub frequency: f32,
+    /// Part of the period square wave is high. Other waveforms ignore it.
+    pub duty:      f32,
+
+    phase:      f32,
+    rng:        StdRng,
+    noise:      f32,
+    noise_step: u32,
+}
+
+impl Oscillator {
+    pub fn new(waveform: Waveform, frequency: f32) -> Self {
+        Self::with_seed(waveform, frequency, 0)
+    }
+
+    /// Same seed produces same noise.
+    pub fn with_seed(waveform: Waveform, frequency: f32, seed: u64) -> Self {
+        Self {
+            waveform,
+            frequency,
+            duty: 0.5,
+            phase: 0.0,
+            rng: StdRng::seed_from_u64(seed),
+            noise: 0.0,
+            noise_step: u32::MAX,
+        }
+    }
+
+    /// Next sample in `-1.0..=1.0`.
+    pub fn next_sample(&mut self, sample_rate: u32) -> f32 {
+        let phase = self.phase;
+        self.phase = (self.phase + self.frequency / sample_rate as f32).fract();
+
+        match self.waveform {
+            Waveform::Square => {
+                if phase < self.duty {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Saw => 1.0 - phase * 2.0,
+            Waveform::Sine => (phase * TAU).sin(),
+            Waveform::Noise => {
+                let step = (phase * NOISE_STEPS) as u32;
+                if step != self.noise_step {
+                    self.noise_step = step;
+                    self.noise = self.rng.gen_range(-1.0..=1.0);
+                }
+                self.noise
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::sfx::{Envelope, Waveform};
+
+/// Description of a retro sound effect in the spirit of sfxr. Presets
+/// generate random variations of common effects, same seed gives the same
+/// effect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SfxParams {
+    /// Seed of noise waveform.
+    pub seed:            u64,
+    pub waveform:        Waveform,
+    /// Start frequency in Hz.
+    pub frequency:       f32,
+    /// Sound stops when sliding below this frequency. `0` to disable.
+    pub min_frequency:   f32,
+    /// Octaves per second. Negative slides down.
+    pub frequency_slide: f32,
+    pub duty:            f32,
+    /// Duty change per second.
+    pub duty_sweep:      f32,
+    /// Part of frequency vibrato changes it by.
+    pub vibrato_depth:   f32,
+    /// Vibrato frequency in Hz.
+    pub vibrato_speed:   f32,
+    /// Frequency is multiplied by this after `arpeggio_time`. `1` to disable.
+    pub arpeggio:        f32,
+    pub arpeggio_time:   f32,
+    /// Low pass filter strength in `0.0..=1.0`. `1` doesn't filter.
+    pub lowpass:         f32,
+    pub envelope:        Envelope,
+    pub volume:          f32,
+}
+
+impl SfxParams {
+    pub fn coin(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut params = Self {
+            seed,
+            frequency: rng.gen_range(700.0..1400.0),
+            envelope: Envelope {
+                attack:  0.0,
+                sustain: rng.gen_range(0.02..0.08),
+                punch:   rng.gen_range(0.3..0.6),
+                decay:   rng.gen_range(0.1..0.3),
+            },
+            ..Default::default()
+        };
+
+        if rng.gen_bool(0.5) {
+            params.arpeggio = rng.gen_range(1.3..1.8);
+            params.arpeggio_time = rng.gen_range(0.04..0.08);
+        }
+
+        params
+    }
+
+    pub fn laser(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let frequency = rng.gen_range(500.0..1800.0);
+
+        Self {
+            seed,
+            waveform: [Waveform::Square, Waveform::Saw, Waveform::Sine][rng.gen_range(0..3)],
+            frequency,
+            min_frequency: rng.gen_range(0.0..frequency / 4.0),
+            frequency_slide: rng.gen_range(-8.0..-2.0),
+            duty: rng.gen_range(0.1..0.5),
+            duty_sweep: rng.gen_range(0.0..0.5),
+            envelope: Envelope {
+                attack:  0.0,
+                sustain: rng.gen_range(0.05..0.15),
+                punch:   rng.gen_range(0.0..0.3),
+                decay:   rng.gen_range(0.05..0.25),
+            },
+            ..Default::default()
+        }
+    }
+
+    pub fn explosion(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        Self {
+            seed,
+            waveform: Waveform::Noise,
+            frequency: rng.gen_range(80.0..500.0),
+            frequency_slide: rng.gen_range(-1.5..0.0),
+            vibrato_depth: if rng.gen_bool(0.5) {
+                rng.gen_range(0.0..0.3)
+            } else {
+                0.0
+            },
+            vibrato_speed: rng.gen_range(5.0..20.0),
+            lowpass: rng.gen_range(0.3..1.0),
+            envelope: Envelope {
+                attack:  0.0,
+                sustain: rng.gen_range(0.1..0.35),
+                punch:   rng.gen_range(0.2..0.6),
+                decay:   rng.gen_range(0.3..0.8),
+            },
+            ..Default::default()
+        }
+    }
+
+    pub fn jump(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        Self {
+            seed,
+            frequency: rng.gen_range(250.0..600.0),
+            frequency_slide: rng.gen_range(1.0..3.0),
+            duty: rng.gen_range(0.2..0.5),
+            lowpass: if rng.gen_bool(0.5) {
+                rng.gen_range(0.4..1.0)
+            } else {
+                1.0
+            },
+            envelope: Envelope {
+                attack:  0.0,
+                sustain: rng.gen_range(0.1..0.25),
+                punch:   0.0,
+                decay:   rng.gen_range(0.1..0.3),
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Anything goes. Most results are noisy but some are usable.
+    pub fn random(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        Self {
+            seed,
+            waveform: [Waveform::Square, Waveform::Saw, Waveform::Sine, Waveform::Noise][rng.gen_range(0..4)],
+            frequency: rng.gen_range(50.0..2000.0),
+            frequency_slide: rng.gen_range(-4.0..4.0),
+            duty: rng.gen_range(0.0..1.0),
+            duty_sweep: rng.gen_range(-1.0..1.0),
+            vibrato_depth: rng.gen_range(0.0..0.5),
+            vibrato_speed: rng.gen_range(0.0..30.0),
+            arpeggio: rng.gen_range(0.5..2.0),
+            arpeggio_time: rng.gen_range(0.0..0.3),
+            lowpass: rng.gen_range(0.1..1.0),
+            envelope: Envelope {
+                attack:  rng.gen_range(0.0..0.2),
+                sustain: rng.gen_range(0.05..0.4),
+                punch:   rng.gen_range(0.0..0.5),
+                decay:   rng.gen_range(0.05..0.5),
+            },
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for SfxParams {
+    fn default() -> Self {
+        Self {
+            seed:            0,
+            waveform:        Waveform::Square,
+            frequency:       440.0,
+            min_frequency:   0.0,
+            frequency_slide: 0.0,
+            duty:            0.5,
+            duty_sweep:      0.0,
+            vibrato_depth:   0.0,
+            vibrato_speed:   0.0,
+            arpeggio:        1.0,
+            arpeggio_time:   0.0,
+            lowpass:         1.0,
+            envelope:        Envelope::default(),
+            volume:          0.5,
+        }
+    }
+}
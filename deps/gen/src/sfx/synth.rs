@@ -0,0 +1,175 @@
+use std::{
+    f32::consts::TAU,
+    fs::File,
+    io::{self, Cursor, Seek, Write},
+    path::Path,
+};
+
+use hound::{SampleFormat, WavSpec, WavWriter};
+
+use crate::sfx::{Oscillator, SfxParams};
+
+pub const SFX_SAMPLE_RATE: u32 = 44_100;
+
+/// Mono samples of a generated effect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SfxData {
+    pub samples:     Vec<f32>,
+    pub sample_rate: u32,
+}
+
+impl SfxData {
+    pub fn duration(&self) -> f32 {
+        self.samples.len() as f32 / self.sample_rate as f32
+    }
+
+    /// 16 bit WAV file contents. Can be played with
+    /// `audio::Sound::load(&data.to_wav(), "name")`.
+    pub fn to_wav(&self) -> Vec<u8> {
+        let mut wav = Cursor::new(vec![]);
+        self.write(&mut wav).expect("Failed to write WAV to memory");
+        wav.into_inner()
+    }
+
+    pub fn save_wav(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write(&mut File::create(path)?)
+    }
+
+    fn write(&self, writer: &mut (impl Write + Seek)) -> io::Result<()> {
+        let spec = WavSpec {
+            channels:        1,
+            sample_rate:     self.sample_rate,
+            bits_per_sample: 16,
+            sample_format:   SampleFormat::Int,
+        };
+
+        let mut wav = WavWriter::new(writer, spec).map_err(io::Error::other)?;
+
+        for sample in &self.samples {
+            let sample = (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+            wav.write_sample(sample).map_err(io::Error::other)?;
+        }
+
+        wav.finalize().map_err(io::Error::other)
+    }
+}
+
+pub fn generate_sfx(params: &SfxParams) -> SfxData {
+    let rate = SFX_SAMPLE_RATE as f32;
+    let length = (params.envelope.duration() * rate) as usize;
+
+    let mut oscillator = Oscillator::with_seed(params.waveform, params.frequency, params.seed);
+    let mut filtered = 0.0;
+    let mut samples = Vec::with_capacity(length);
+
+    for i in 0..length {
+        let time = i as f32 / rate;
+
+        let mut frequency = params.frequency * (params.frequency_slide * time).exp2();
+
+        if params.min_frequency > 0.0 && frequency < params.min_frequency {
+            break;
+        }
+
+        if params.arpeggio_time > 0.0 && time >= params.arpeggio_time {
+            frequency *= params.arpeggio;
+        }
+
+        frequency *= 1.0 + params.vibrato_depth * (time * params.vibrato_speed * TAU).sin();
+
+        oscillator.frequency = frequency.max(0.0);
+        oscillator.duty = (params.duty + params.duty_sweep * time).clamp(0.0, 1.0);
+
+        let sample = oscillator.next_sample(SFX_SAMPLE_RATE);
+        filtered += (sample - filtered) * params.lowpass.clamp(0.0, 1.0);
+
+        samples.push(filtered * params.envelope.amplitude(time) * params.volume);
+    }
+
+    SfxData {
+        samples,
+        sample_rate: SFX_SAMPLE_RATE,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use hound::WavReader;
+
+    use crate::sfx::{generate_sfx, SfxParams, Waveform, SFX_SAMPLE_RATE};
+
+    fn presets(seed: u64) -> [SfxParams; 5] {
+        [
+            SfxParams::coin(seed),
+            SfxParams::laser(seed),
+            SfxParams::explosion(seed),
+            SfxParams::jump(seed),
+            SfxParams::random(seed),
+        ]
+    }
+
+    #[test]
+    fn same_seed() {
+        for seed in [0, 1, 42] {
+            for (params, again) in presets(seed).iter().zip(presets(seed)) {
+                assert_eq!(params, &again);
+                assert_eq!(generate_sfx(params), generate_sfx(&again));
+            }
+        }
+
+        let noise = |seed| {
+            generate_sfx(&SfxParams {
+                seed,
+                waveform: Waveform::Noise,
+                ..Default::default()
+            })
+        };
+
+        assert_eq!(noise(5), noise(5));
+        assert_ne!(noise(5), noise(6));
+    }
+
+    #[test]
+    fn length() {
+        let params = SfxParams::default();
+        let data = generate_sfx(&params);
+
+        assert_eq!(data.sample_rate, SFX_SAMPLE_RATE);
+        assert_eq!(
+            data.samples.len(),
+            (params.envelope.duration() * SFX_SAMPLE_RATE as f32) as usize
+        );
+        assert!((data.duration() - params.envelope.duration()).abs() < 0.001);
+
+        // Sounds that slide below minimum frequency stop early.
+        for params in presets(7) {
+            let data = generate_sfx(&params);
+            assert!(!data.samples.is_empty());
+            assert!(data.duration() <= params.envelope.duration());
+            if params.min_frequency == 0.0 {
+                assert!((data.duration() - params.envelope.duration()).abs() < 0.001);
+            }
+        }
+    }
+
+    #[test]
+    fn wav() {
+        let data = generate_sfx(&SfxParams::coin(3));
+        let mut reader = WavReader::new(Cursor::new(data.to_wav())).unwrap();
+
+        let spec = reader.spec();
+        assert_eq!(spec.channels, 1);
+        assert_eq!(spec.sample_rate, SFX_SAMPLE_RATE);
+        assert_eq!(spec.bits_per_sample, 16);
+
+        let samples: Vec<i16> = reader.samples().map(Result::unwrap).collect();
+        assert_eq!(samples.len(), data.samples.len());
+
+        for (read, sample) in samples.iter().zip(&data.samples) {
+            let expected = (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+            assert_eq!(*read, expected);
+        }
+    }
+}
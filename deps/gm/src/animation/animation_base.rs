@@ -0,0 +1,223 @@
+use std::time::Duration;
+
+use crate::{Clock, Easing, Keyframes, Lerp, LossyConvert};
+
+/// How animation continues after `duration`.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub enum AnimationMode {
+    /// Plays once and stays at the end value.
+    #[default]
+    Once,
+    /// Starts over from the beginning forever.
+    Loop,
+    /// Plays forward and backward forever.
+    PingPong,
+    /// Plays given number of times and stays at the end value.
+    Repeat(u32),
+}
+
+#[derive(Clone, Debug)]
+pub struct Animation<T = f32> {
+    track:    Keyframes<T>,
+    duration: f32,
+    easing:   Easing,
+    mode:     AnimationMode,
+    stamp:    Duration,
+}
+
+impl<T: Lerp> Animation<T> {
+    /// Linear animation from `start` to `end` played once.
+    pub fn new(start: T, end: T, duration: impl Into<f32>) -> Self {
+        Self::with_keyframes(Keyframes::new(start, end), duration)
+    }
+
+    pub fn with_keyframes(track: Keyframes<T>, duration: impl Into<f32>) -> Self {
+        let duration = duration.into();
+        assert!(duration > 0.0, "Animation duration must be positive");
+        Self {
+            track,
+            duration,
+            easing: Easing::default(),
+            mode: AnimationMode::default(),
+            stamp: Clock::elapsed(),
+        }
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn mode(mut self, mode: AnimationMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Starts animation from the beginning.
+    pub fn restart(&mut self) {
+        self.stamp = Clock::elapsed();
+    }
+
+    /// Always `false` for [`AnimationMode::Loop`] and
+    /// [`AnimationMode::PingPong`].
+    pub fn finished(&self) -> bool {
+        let cycles = match self.mode {
+            AnimationMode::Once => 1,
+            AnimationMode::Repeat(count) => count,
+            AnimationMode::Loop | AnimationMode::PingPong => return false,
+        };
+
+        self.elapsed() >= self.duration * cycles.lossy_convert()
+    }
+
+    /// Position on the track before easing. `0.0..=1.0`.
+    pub fn progress(&self) -> f32 {
+        if self.finished() {
+            return 1.0;
+        }
+
+        let elapsed = self.elapsed();
+        let cycle: u64 = (elapsed / self.duration).lossy_convert();
+        let cycle_start: f32 = cycle.lossy_convert();
+        let progress = (elapsed / self.duration - cycle_start).clamp(0.0, 1.0);
+
+        if self.mode == AnimationMode::PingPong && cycle % 2 == 1 {
+            1.0 - progress
+        } else {
+            progress
+        }
+    }
+
+    pub fn value(&self) -> T {
+        self.track.value(self.easing.apply(self.progress()))
+    }
+
+    fn elapsed(&self) -> f32 {
+        Clock::elapsed().saturating_sub(self.stamp).as_secs_f32()
+    }
+}
+
+impl<T: Lerp + Default> Default for Animation<T> {
+    fn default() -> Self {
+        Self::new(T::default(), T::default(), 1.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{
+        clock::test::lock_clock,
+        flat::{Point, Rect},
+        Animation, AnimationMode, Clock, Color, Easing, Keyframes,
+    };
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.001
+    }
+
+    #[test]
+    fn test() {
+        let _lock = lock_clock();
+        Clock::set_manual(true);
+
+        let anim = Animation::new(0.0, 1.0, 0.5).mode(AnimationMode::PingPong);
+
+        assert_eq!(anim.value(), 0.0);
+        assert!(!anim.finished());
+
+        Clock::advance(Duration::from_secs_f32(0.25));
+
+        assert!(!anim.finished());
+        assert!(close(anim.value(), 0.5));
+
+        Clock::advance(Duration::from_secs_f32(0.10));
+
+        assert!(!anim.finished());
+        assert!(close(anim.value(), 0.7));
+
+        Clock::advance(Duration::from_secs_f32(0.15));
+
+        assert!(!anim.finished());
+        assert!(anim.value() <= 0.001 || anim.value() >= 0.999);
+
+        Clock::advance(Duration::from_secs_f32(0.25));
+
+        assert!(!anim.finished());
+        assert!(close(anim.value(), 0.5));
+
+        Clock::set_manual(false);
+    }
+
+    #[test]
+    fn modes() {
+        let _lock = lock_clock();
+        Clock::set_manual(true);
+
+        let once = Animation::new(0.0, 10.0, 1.0);
+        let looped = Animation::new(0.0, 10.0, 1.0).mode(AnimationMode::Loop);
+        let repeat = Animation::new(0.0, 10.0, 1.0).mode(AnimationMode::Repeat(2));
+
+        Clock::advance(Duration::from_secs_f32(1.25));
+
+        assert!(once.finished());
+        assert!(close(once.value(), 10.0));
+        assert!(!looped.finished());
+        assert!(close(looped.value(), 2.5));
+        assert!(!repeat.finished());
+        assert!(close(repeat.value(), 2.5));
+
+        Clock::advance(Duration::from_secs_f32(1.0));
+
+        assert!(repeat.finished());
+        assert!(close(repeat.value(), 10.0));
+
+        Clock::set_manual(false);
+    }
+
+    #[test]
+    fn easing() {
+        for easing in [
+            Easing::Linear,
+            Easing::QuadIn,
+            Easing::QuadOut,
+            Easing::QuadInOut,
+            Easing::CubicIn,
+            Easing::CubicOut,
+            Easing::CubicInOut,
+            Easing::ElasticIn,
+            Easing::ElasticOut,
+            Easing::BounceIn,
+            Easing::BounceOut,
+            Easing::Bezier(0.25, 0.1, 0.25, 1.0),
+        ] {
+            assert!(close(easing.apply(0.0), 0.0), "{easing:?}");
+            assert!(close(easing.apply(1.0), 1.0), "{easing:?}");
+        }
+
+        assert!(close(Easing::QuadIn.apply(0.5), 0.25));
+        assert!(close(Easing::CubicOut.apply(0.5), 0.875));
+        assert!(close(Easing::BounceOut.apply(0.5), 0.765_625));
+        assert!(close(Easing::Bezier(0.0, 0.0, 1.0, 1.0).apply(0.3), 0.3));
+        assert!(Easing::ElasticOut.apply(0.2) > 1.0);
+    }
+
+    #[test]
+    fn keyframes() {
+        let track = Keyframes::new(0.0, 10.0).add(0.5, 20.0);
+
+        assert!(close(track.value(0.25), 10.0));
+        assert!(close(track.value(0.5), 20.0));
+        assert!(close(track.value(0.75), 15.0));
+
+        let track: Keyframes<Point> = [(1.0, (10, 10).into()), (0.0, (0, 0).into())].into_iter().collect();
+        assert_eq!(track.value(0.5), Point::new(5.0, 5.0));
+
+        let track = Keyframes::new(Rect::new(0.0, 0.0, 10.0, 10.0), Rect::new(10.0, 10.0, 20.0, 30.0));
+        assert_eq!(track.value(0.5), Rect::new(5.0, 5.0, 15.0, 20.0));
+
+        let track = Keyframes::new(Color::BLACK, Color::WHITE);
+        assert_eq!(track.value(0.5), Color::rgb(0.5, 0.5, 0.5));
+    }
+}
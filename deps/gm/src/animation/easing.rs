@@ -0,0 +1,111 @@
+use std::f32::consts::PI;
+
+/// Maps linear animation progress to eased progress. Both are `0.0` at the
+/// start and `1.0` at the end. Elastic easings overshoot in between.
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    ElasticIn,
+    ElasticOut,
+    BounceIn,
+    BounceOut,
+    /// Same as CSS `cubic-bezier(x1, y1, x2, y2)`. `x` values are clamped to
+    /// `0.0..=1.0`.
+    Bezier(f32, f32, f32, f32),
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Self::Linear => t,
+            Self::QuadIn => t * t,
+            Self::QuadOut => 1.0 - (1.0 - t).powi(2),
+            Self::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Self::CubicIn => t.powi(3),
+            Self::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Self::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t.powi(3)
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Self::ElasticIn => 1.0 - elastic_out(1.0 - t),
+            Self::ElasticOut => elastic_out(t),
+            Self::BounceIn => 1.0 - bounce_out(1.0 - t),
+            Self::BounceOut => bounce_out(t),
+            Self::Bezier(x1, y1, x2, y2) => bezier(x1.clamp(0.0, 1.0), y1, x2.clamp(0.0, 1.0), y2, t),
+        }
+    }
+}
+
+fn elastic_out(t: f32) -> f32 {
+    if t <= 0.0 || t >= 1.0 {
+        return t;
+    }
+    2.0f32.powf(-10.0 * t) * ((10.0 * t - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984_375
+    }
+}
+
+/// Curve from `(0, 0)` to `(1, 1)` with control points `(x1, y1)` and
+/// `(x2, y2)`. Finds curve parameter for `x` and returns `y` at it.
+fn bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    let curve = |a: f32, b: f32, s: f32| {
+        let inv = 1.0 - s;
+        3.0 * inv * inv * s * a + 3.0 * inv * s * s * b + s.powi(3)
+    };
+
+    // x(s) is monotonic for x1, x2 in 0..=1 so bisection always converges.
+    let (mut low, mut high) = (0.0f32, 1.0f32);
+    let mut s = x;
+
+    for _ in 0..32 {
+        let value = curve(x1, x2, s);
+
+        if (value - x).abs() < 1e-6 {
+            break;
+        }
+
+        if value < x {
+            low = s;
+        } else {
+            high = s;
+        }
+
+        s = (low + high) / 2.0;
+    }
+
+    curve(y1, y2, s)
+}
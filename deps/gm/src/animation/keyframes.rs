@@ -0,0 +1,73 @@
+use crate::Lerp;
+
+/// Values at points of animation progress. Value between keyframes is
+/// interpolated linearly.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keyframes<T> {
+    /// Sorted by progress.
+    frames: Vec<(f32, T)>,
+}
+
+impl<T: Lerp> Keyframes<T> {
+    /// Track from `start` at `0.0` to `end` at `1.0`.
+    pub fn new(start: T, end: T) -> Self {
+        Self {
+            frames: vec![(0.0, start), (1.0, end)],
+        }
+    }
+
+    /// Adds value at `at` progress. `at` is clamped to `0.0..=1.0`. Replaces
+    /// value of a keyframe at the same progress.
+    pub fn add(mut self, at: f32, value: T) -> Self {
+        let at = at.clamp(0.0, 1.0);
+        let index = self.frames.partition_point(|(progress, _)| *progress < at);
+
+        match self.frames.get_mut(index) {
+            Some(frame) if frame.0.total_cmp(&at).is_eq() => frame.1 = value,
+            _ => self.frames.insert(index, (at, value)),
+        }
+
+        self
+    }
+
+    /// Value at `progress`. Goes past the first and last keyframes for
+    /// progress out of `0.0..=1.0`.
+    pub fn value(&self, progress: f32) -> T {
+        let last = self.frames.len() - 1;
+
+        if last == 0 {
+            return self.frames[0].1.clone();
+        }
+
+        let index = self.frames[1..last].partition_point(|(at, _)| *at < progress);
+        let (from_at, from) = &self.frames[index];
+        let (to_at, to) = &self.frames[index + 1];
+
+        let span = to_at - from_at;
+        let t = if span > 0.0 {
+            (progress - from_at) / span
+        } else {
+            1.0
+        };
+
+        from.lerp(to, t)
+    }
+}
+
+impl<T: Lerp> FromIterator<(f32, T)> for Keyframes<T> {
+    /// Panics if there are no keyframes.
+    fn from_iter<I: IntoIterator<Item = (f32, T)>>(iter: I) -> Self {
+        let mut iter = iter.into_iter();
+        let (at, first) = iter.next().expect("Keyframes need at least one value");
+
+        let mut keyframes = Self {
+            frames: vec![(at.clamp(0.0, 1.0), first)],
+        };
+
+        for (at, value) in iter {
+            keyframes = keyframes.add(at, value);
+        }
+
+        keyframes
+    }
+}
@@ -0,0 +1,54 @@
+use crate::{
+    flat::{Point, Rect, Size},
+    Color,
+};
+
+/// Linear interpolation. `t` may be out of `0.0..=1.0` for easings that
+/// overshoot like [`crate::Easing::ElasticOut`].
+pub trait Lerp: Clone {
+    fn lerp(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for Point {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        Self {
+            x: self.x.lerp(&to.x, t),
+            y: self.y.lerp(&to.y, t),
+        }
+    }
+}
+
+impl Lerp for Size {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        Self {
+            width:  self.width.lerp(&to.width, t),
+            height: self.height.lerp(&to.height, t),
+        }
+    }
+}
+
+impl Lerp for Rect {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        Self {
+            origin: self.origin.lerp(&to.origin, t),
+            size:   self.size.lerp(&to.size, t),
+        }
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        Self::rgba(
+            self.r.lerp(&to.r, t),
+            self.g.lerp(&to.g, t),
+            self.b.lerp(&to.b, t),
+            self.a.lerp(&to.a, t),
+        )
+    }
+}
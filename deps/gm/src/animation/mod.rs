@@ -0,0 +1,9 @@
+mod animation_base;
+mod easing;
+mod keyframes;
+mod lerp;
+
+pub use animation_base::*;
+pub use easing::Easing;
+pub use keyframes::Keyframes;
+pub use lerp::Lerp;
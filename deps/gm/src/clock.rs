@@ -0,0 +1,155 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, MutexGuard, OnceLock,
+    },
+    time::Duration,
+};
+
+use web_time::Instant;
+
+struct State {
+    /// Time is frozen at this value in manual mode.
+    manual:         Option<Duration>,
+    /// Clock and real time when real mode was last resumed. Clock continues
+    /// from where manual mode left it.
+    resumed_clock:  Duration,
+    resumed_real:   Duration,
+    frame_duration: Option<Duration>,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    manual:         None,
+    resumed_clock:  Duration::ZERO,
+    resumed_real:   Duration::ZERO,
+    frame_duration: None,
+});
+
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+static FRAME: AtomicU64 = AtomicU64::new(0);
+
+fn state() -> MutexGuard<'static, State> {
+    STATE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn epoch() -> Instant {
+    *EPOCH.get_or_init(Instant::now)
+}
+
+impl State {
+    fn elapsed(&self) -> Duration {
+        self.manual
+            .unwrap_or_else(|| self.resumed_clock + epoch().elapsed().saturating_sub(self.resumed_real))
+    }
+}
+
+/// Engine time. Animations, timers and frame counters use it instead of
+/// system time so tests can switch it to manual mode and get the same
+/// results on every run.
+pub struct Clock;
+
+impl Clock {
+    pub fn now() -> Instant {
+        epoch() + Self::elapsed()
+    }
+
+    /// Time since the clock was first used.
+    pub fn elapsed() -> Duration {
+        state().elapsed()
+    }
+
+    /// Number of frames since start.
+    pub fn frame() -> u64 {
+        FRAME.load(Ordering::Relaxed)
+    }
+
+    /// Called by the frame loop once per frame. Advances manual clock by
+    /// frame duration if it is set.
+    pub fn next_frame() {
+        FRAME.fetch_add(1, Ordering::Relaxed);
+
+        let state = &mut *state();
+        if let (Some(manual), Some(frame)) = (&mut state.manual, state.frame_duration) {
+            *manual += frame;
+        }
+    }
+
+    pub fn is_manual() -> bool {
+        state().manual.is_some()
+    }
+
+    /// In manual mode time stands still and moves only with
+    /// [`Clock::advance`] and [`Clock::next_frame`]. Time never goes back
+    /// when mode is switched.
+    pub fn set_manual(manual: bool) {
+        let mut state = state();
+
+        if manual {
+            state.manual = Some(state.elapsed());
+        } else if let Some(elapsed) = state.manual.take() {
+            state.resumed_clock = elapsed;
+            state.resumed_real = epoch().elapsed();
+        }
+    }
+
+    /// Time every [`Clock::next_frame`] adds in manual mode. `None` makes
+    /// frames take no time.
+    pub fn set_frame_duration(duration: Option<Duration>) {
+        state().frame_duration = duration;
+    }
+
+    /// Moves the clock forward. Works in both modes.
+    pub fn advance(duration: Duration) {
+        let state = &mut *state();
+
+        match &mut state.manual {
+            Some(manual) => *manual += duration,
+            None => state.resumed_clock += duration,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::{
+        sync::{Mutex, MutexGuard},
+        thread::sleep,
+        time::Duration,
+    };
+
+    use crate::Clock;
+
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    /// Clock is global so tests that change it can't run in parallel.
+    pub(crate) fn lock_clock() -> MutexGuard<'static, ()> {
+        LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    #[test]
+    fn manual_clock() {
+        let _lock = lock_clock();
+
+        Clock::set_manual(true);
+        let start = Clock::elapsed();
+
+        sleep(Duration::from_millis(20));
+        assert_eq!(Clock::elapsed(), start);
+
+        Clock::advance(Duration::from_millis(500));
+        assert_eq!(Clock::elapsed(), start + Duration::from_millis(500));
+
+        Clock::set_frame_duration(Some(Duration::from_millis(10)));
+        let frame = Clock::frame();
+        Clock::next_frame();
+        Clock::next_frame();
+        Clock::set_frame_duration(None);
+
+        assert_eq!(Clock::frame(), frame + 2);
+        assert_eq!(Clock::elapsed(), start + Duration::from_millis(520));
+
+        Clock::set_manual(false);
+        sleep(Duration::from_millis(20));
+        assert!(Clock::elapsed() >= start + Duration::from_millis(540));
+    }
+}
@@ -6,6 +6,7 @@ extern crate core;
 
 mod animation;
 pub mod axis;
+mod clock;
 mod color;
 pub mod converter;
 pub mod flat;
@@ -14,7 +15,8 @@ mod num;
 pub mod sign;
 pub mod volume;
 
-pub use animation::Animation;
+pub use animation::{Animation, AnimationMode, Easing, Keyframes, Lerp};
+pub use clock::Clock;
 pub use color::*;
 pub use misc::{Apply, Platform, Toggle};
 pub use num::{
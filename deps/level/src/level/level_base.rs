@@ -15,7 +15,7 @@ use refs::{Own, Weak};
 use vents::Event;
 use wgpu_wrapper::image::Image;
 
-use crate::{event_handler::EventHandler, sets::Sets, Level, Player, Sprite};
+use crate::{event_handler::EventHandler, sets::Sets, sound_emitter::SoundEmitter, Level, Player, Sprite};
 
 #[derive(Educe)]
 #[educe(Default)]
@@ -34,6 +34,8 @@ pub struct LevelBase {
     pub(crate) sprites: Vec<Own<dyn Sprite>>,
     pub(crate) sets:    Sets,
 
+    pub(crate) sound_emitters: Vec<SoundEmitter>,
+
     #[educe(Default = Vector2::new(0.0, -9.81))]
     pub(crate) gravity: Vector2<f32>,
 
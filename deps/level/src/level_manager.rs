@@ -1,5 +1,6 @@
 use std::ops::{Deref, DerefMut};
 
+use audio::{Playable, Playback};
 use educe::Educe;
 use gm::{flat::Point, LossyConvert};
 use rapier2d::{
@@ -9,7 +10,7 @@ use rapier2d::{
 use refs::{MainLock, Own, Weak};
 use wgpu_wrapper::WGPUApp;
 
-use crate::Level;
+use crate::{sound_emitter::SoundEmitter, Level};
 
 static SELF: MainLock<LevelManager> = MainLock::new();
 
@@ -30,6 +31,7 @@ impl LevelManager {
         }
 
         Self::level().__internal_update(frame_time);
+        unsafe { Self::level_unchecked() }.update_sounds();
     }
 }
 
@@ -85,6 +87,12 @@ impl LevelManager {
         &mut SELF.get_mut().camera_pos
     }
 
+    /// Plays `sound` as if it came from `position` of the level. Volume and
+    /// pan depend on where the position is on screen.
+    pub fn play_sound_at(sound: &dyn Playable, position: impl Into<Point>) -> Playback {
+        SoundEmitter::play(sound, None, position.into())
+    }
+
     pub fn convert_touch(pos: Point) -> Point {
         let mut pos = pos;
         let size = WGPUApp::current().window_size;
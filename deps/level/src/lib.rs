@@ -10,6 +10,7 @@ mod event_handler;
 mod level;
 mod level_manager;
 mod sets;
+mod sound_emitter;
 mod sprite_data;
 mod to_collider;
 mod units;
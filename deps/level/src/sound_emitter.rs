@@ -0,0 +1,109 @@
+use audio::{Playable, Playback};
+use gm::{flat::Point, LossyConvert};
+use wgpu_wrapper::WGPUApp;
+
+use crate::{LevelBase, LevelManager};
+
+/// Sounds are played at full volume while their source is on screen. Outside
+/// of the screen they fade out over this many half screen sizes.
+const ROLLOFF: f32 = 2.0;
+
+/// Sound played from a point of the level. Follows the sprite it was played
+/// by until the sprite is removed.
+pub(crate) struct SoundEmitter {
+    /// Address of the sprite.
+    sprite:   Option<usize>,
+    position: Point,
+    playback: Playback,
+}
+
+impl SoundEmitter {
+    pub(crate) fn play(sound: &dyn Playable, sprite: Option<usize>, position: Point) -> Playback {
+        let playback = sound.play();
+        apply_position(&playback, position);
+
+        if !LevelManager::no_level() {
+            unsafe { LevelManager::level_unchecked() }.sound_emitters.push(Self {
+                sprite,
+                position,
+                playback: playback.clone(),
+            });
+        }
+
+        playback
+    }
+}
+
+impl LevelBase {
+    /// Moves playing sounds after their sprites and the camera.
+    pub(crate) fn update_sounds(&mut self) {
+        self.sound_emitters.retain(|emitter| !emitter.playback.is_finished());
+
+        for emitter in &mut self.sound_emitters {
+            if let Some(address) = emitter.sprite {
+                match self.sprites.iter().find(|sprite| sprite.addr() == address) {
+                    Some(sprite) => emitter.position = sprite.position(),
+                    None => emitter.sprite = None,
+                }
+            }
+
+            apply_position(&emitter.playback, emitter.position);
+        }
+    }
+}
+
+/// Pan and volume of a sound at `position` as heard from camera position.
+fn apply_position(playback: &Playback, position: Point) {
+    let window_size = WGPUApp::current().window_size;
+    let screen_scale: f32 = WGPUApp::screen_scale().lossy_convert();
+
+    // Inverse of `LevelManager::convert_touch`: offset from screen center
+    // relative to half of the screen size.
+    let offset = (position - *LevelManager::camera_pos()) * *LevelManager::scale() * screen_scale * 10.0;
+    let offset = Point::new(offset.x / window_size.width, offset.y / window_size.height);
+
+    let (pan, attenuation) = pan_and_attenuation(offset);
+
+    playback.set_pan(pan);
+    playback.set_attenuation(attenuation);
+}
+
+/// `offset` is relative to half of the screen size. Sounds beyond the screen
+/// edge are panned fully to that side.
+fn pan_and_attenuation(offset: Point) -> (f32, f32) {
+    let distance = offset.x.hypot(offset.y);
+    let attenuation = 1.0 - (distance - 1.0).max(0.0) / ROLLOFF;
+    (offset.x.clamp(-1.0, 1.0), attenuation.max(0.0))
+}
+
+#[cfg(test)]
+mod test {
+    use gm::flat::Point;
+
+    use crate::sound_emitter::{pan_and_attenuation, ROLLOFF};
+
+    #[test]
+    fn attenuation() {
+        let attenuation = |x: f32, y: f32| pan_and_attenuation(Point::new(x, y)).1;
+
+        // Full volume anywhere on screen.
+        assert_eq!(attenuation(0.0, 0.0), 1.0);
+        assert_eq!(attenuation(1.0, 0.0), 1.0);
+        assert_eq!(attenuation(0.0, -1.0), 1.0);
+        assert_eq!(attenuation(0.6, 0.8), 1.0);
+
+        // Linear fade outside of the screen.
+        assert_eq!(attenuation(1.0 + ROLLOFF / 2.0, 0.0), 0.5);
+        assert_eq!(attenuation(0.0, 1.0 + ROLLOFF), 0.0);
+        assert_eq!(attenuation(-1.0 - ROLLOFF * 0.75, 0.0), 0.25);
+        assert_eq!(attenuation(1.0 + ROLLOFF * 2.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn pan() {
+        assert_eq!(pan_and_attenuation(Point::new(-0.5, 3.0)).0, -0.5);
+        assert_eq!(pan_and_attenuation(Point::new(0.0, -1.0)).0, 0.0);
+        assert_eq!(pan_and_attenuation(Point::new(4.0, 0.0)).0, 1.0);
+        assert_eq!(pan_and_attenuation(Point::new(-1.5, 0.0)).0, -1.0);
+    }
+}
@@ -3,6 +3,7 @@ use std::{
     ops::{Deref, DerefMut},
 };
 
+use audio::{Playable, Playback};
 use gm::{
     flat::{Point, Shape, Size},
     Color, ToF32,
@@ -16,7 +17,7 @@ use rapier2d::{
 use refs::{weak_from_ref, Address, Own};
 use wgpu_wrapper::image::ToImage;
 
-use crate::{LevelManager, SpriteData};
+use crate::{sound_emitter::SoundEmitter, LevelManager, SpriteData};
 
 pub trait Sprite: Deref<Target = SpriteData> + DerefMut {
     fn make(shape: Shape, position: Point) -> Own<Self>
@@ -131,6 +132,12 @@ pub trait Sprite: Deref<Target = SpriteData> + DerefMut {
         self.is_selected
     }
 
+    /// Plays `sound` from position of this sprite. The sound follows the
+    /// sprite while it plays.
+    fn play_sound(&self, sound: &dyn Playable) -> Playback {
+        SoundEmitter::play(sound, Some(self.address()), self.position())
+    }
+
     fn remove(&mut self) {
         let address = self.address();
         LevelManager::level_weak().remove(address);
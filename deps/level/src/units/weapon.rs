@@ -1,5 +1,6 @@
 use std::ops::{Deref, DerefMut};
 
+use audio::SoundGroup;
 use gm::{
     flat::{Point, Shape},
     Color,
@@ -16,6 +17,7 @@ pub struct Weapon {
     pub bullet_speed:    f32,
     pub bullet_image:    Weak<Image>,
     pub bullet_shape:    Shape,
+    pub shot_sound:      Option<SoundGroup>,
 }
 
 impl Weapon {
@@ -33,6 +35,10 @@ impl Weapon {
         bullet.set_restitution(0.5, CoefficientCombineRule::Average);
         bullet.set_color(Color::random());
         bullet.set_image(self.bullet_image);
+
+        if let Some(sound) = &self.shot_sound {
+            self.play_sound(sound);
+        }
     }
 }
 
@@ -45,6 +51,7 @@ impl Sprite for Weapon {
             bullet_speed: 1.0,
             bullet_image: Weak::default(),
             bullet_shape: Shape::Rect((1, 1).into()),
+            shot_sound:   None,
         })
     }
 }
@@ -0,0 +1,373 @@
+use std::{
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf, MAIN_SEPARATOR_STR},
+};
+
+use chrono::{DateTime, Utc};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+
+use crate::{
+    backend::backend,
+    changes::notify_all,
+    encrypt::is_encrypted,
+    flush_all,
+    storage::{invalidate_all, storage_dir},
+    StoreError, StoreResult,
+};
+
+/// Archive layout: `MAGIC | ARCHIVE_VERSION | deflate(body)`.
+const MAGIC: &[u8] = b"TEA";
+const ARCHIVE_VERSION: u8 = 1;
+
+/// Temporary files and backups are not part of stored data.
+const SKIPPED_SUFFIXES: &[&str] = &[".tmp", ".bak"];
+
+/// Single stored file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveEntry {
+    /// Path relative to storage directory with `/` separators. Same as
+    /// `OnDisk` name or `namespace/name` for namespaced values.
+    pub key:       String,
+    /// Data of `OnDiskEncrypted` values and encrypted save slots. Such data is
+    /// archived as is and can only be read with the key.
+    pub encrypted: bool,
+    pub data:      Vec<u8>,
+}
+
+/// Snapshot of all stored values in a single blob. Used for user backups and
+/// for attaching game state to bug reports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Archive {
+    pub created_at: DateTime<Utc>,
+    /// Namespace the archive was exported from. `None` for whole storage.
+    pub namespace:  Option<String>,
+    pub entries:    Vec<ArchiveEntry>,
+}
+
+impl Archive {
+    /// Collects every stored value. Cached values are flushed first.
+    pub fn export() -> StoreResult<Self> {
+        Self::collect(None)
+    }
+
+    /// Collects values of a single namespace only.
+    pub fn export_namespace(namespace: &str) -> StoreResult<Self> {
+        Self::collect(Some(namespace))
+    }
+
+    /// Replaces stored values with archive contents. Values that are not in
+    /// the archive are removed after all entries are written, only within the
+    /// archive namespace if it has one. Backups are kept. Cached values are
+    /// flushed first and reloaded after restore. Subscribers are notified.
+    pub fn restore(&self) -> StoreResult<()> {
+        flush_all();
+
+        let root = storage_dir()?;
+        let dir = self.dir(&root)?;
+
+        let paths = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let path = root.join(key_path(&entry.key)?);
+                if path == dir || !path.starts_with(&dir) || is_skipped(&path) {
+                    return Err(StoreError::Parse(
+                        format!(
+                            "Archive key {:?} is not a stored value of its namespace",
+                            entry.key
+                        )
+                        .into(),
+                    ));
+                }
+                Ok(path)
+            })
+            .collect::<StoreResult<Vec<_>>>()?;
+
+        for (entry, path) in self.entries.iter().zip(&paths) {
+            backend().write(path, &entry.data)?;
+        }
+
+        for path in backend().list(&dir)? {
+            if !is_skipped(&path) && !paths.contains(&path) {
+                backend().remove(&path)?;
+            }
+        }
+
+        invalidate_all();
+        notify_all();
+
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> StoreResult<Vec<u8>> {
+        let mut body = DeflateEncoder::new(Vec::new(), Compression::default());
+
+        body.write_all(&self.created_at.timestamp_millis().to_le_bytes())?;
+        write_bytes(
+            &mut body,
+            self.namespace.as_deref().unwrap_or_default().as_bytes(),
+        )?;
+        body.write_all(&u64::try_from(self.entries.len()).expect("Too many entries").to_le_bytes())?;
+
+        for entry in &self.entries {
+            write_bytes(&mut body, entry.key.as_bytes())?;
+            body.write_all(&[u8::from(entry.encrypted)])?;
+            write_bytes(&mut body, &entry.data)?;
+        }
+
+        Ok([MAGIC, &[ARCHIVE_VERSION], &body.finish()?].concat())
+    }
+
+    pub fn from_bytes(data: &[u8]) -> StoreResult<Self> {
+        let Some(data) = data.strip_prefix(MAGIC) else {
+            return Err(StoreError::Parse("Not a store archive".into()));
+        };
+
+        match data.split_first() {
+            Some((&ARCHIVE_VERSION, body)) => Self::read_body(&mut DeflateDecoder::new(body))
+                .map_err(|err| StoreError::Parse(format!("Invalid archive: {err}").into())),
+            Some((version, _)) => Err(StoreError::Parse(
+                format!("Unsupported archive version: {version}").into(),
+            )),
+            None => Err(StoreError::Parse("Archive is empty".into())),
+        }
+    }
+
+    fn collect(namespace: Option<&str>) -> StoreResult<Self> {
+        flush_all();
+
+        let archive = Self {
+            created_at: Utc::now(),
+            namespace:  namespace.map(Into::into),
+            entries:    vec![],
+        };
+
+        let root = storage_dir()?;
+        let mut paths = backend().list(&archive.dir(&root)?)?;
+        paths.sort();
+
+        let entries = paths
+            .into_iter()
+            .filter(|path| !is_skipped(path))
+            .map(|path| {
+                let data = backend().read(&path)?;
+                Ok(ArchiveEntry {
+                    key: path_key(path.strip_prefix(&root).expect("Listed file is inside storage dir"))?,
+                    encrypted: is_encrypted(&data),
+                    data,
+                })
+            })
+            .collect::<StoreResult<_>>()?;
+
+        Ok(Self { entries, ..archive })
+    }
+
+    fn dir(&self, root: &Path) -> StoreResult<PathBuf> {
+        Ok(match &self.namespace {
+            Some(namespace) => root.join(key_path(namespace)?),
+            None => root.to_path_buf(),
+        })
+    }
+
+    fn read_body(body: &mut impl Read) -> io::Result<Self> {
+        let created_at = DateTime::from_timestamp_millis(i64::from_le_bytes(read_array(body)?))
+            .ok_or_else(|| invalid_data("Invalid archive date"))?;
+
+        let namespace = read_string(body)?;
+        let count = u64::from_le_bytes(read_array(body)?);
+
+        let entries = (0..count)
+            .map(|_| {
+                Ok(ArchiveEntry {
+                    key:       read_string(body)?,
+                    encrypted: read_array::<1>(body)? != [0],
+                    data:      read_bytes(body)?,
+                })
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(Self {
+            created_at,
+            namespace: (!namespace.is_empty()).then_some(namespace),
+            entries,
+        })
+    }
+}
+
+fn is_skipped(path: &Path) -> bool {
+    let name = path.to_string_lossy();
+    SKIPPED_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+/// Archives can come from other devices so keys are not allowed to point
+/// outside of storage directory.
+fn key_path(key: &str) -> StoreResult<PathBuf> {
+    let path = Path::new(key);
+
+    if key.is_empty() || key.contains('\\') || !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(StoreError::Parse(format!("Invalid archive key: {key:?}").into()));
+    }
+
+    Ok(path.into())
+}
+
+fn path_key(path: &Path) -> StoreResult<String> {
+    let key = path
+        .to_str()
+        .ok_or_else(|| StoreError::Parse(format!("Non UTF-8 file name: {}", path.display()).into()))?;
+    Ok(key.replace(MAIN_SEPARATOR_STR, "/"))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    writer.write_all(&u64::try_from(bytes.len()).expect("Too much data").to_le_bytes())?;
+    writer.write_all(bytes)
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut array = [0; N];
+    reader.read_exact(&mut array)?;
+    Ok(array)
+}
+
+fn read_bytes(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let len = u64::from_le_bytes(read_array(reader)?);
+    let mut bytes = vec![];
+    reader.take(len).read_to_end(&mut bytes)?;
+
+    if bytes.len() as u64 != len {
+        return Err(invalid_data("Archive is truncated"));
+    }
+
+    Ok(bytes)
+}
+
+fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    String::from_utf8(read_bytes(reader)?).map_err(|_| invalid_data("Invalid string"))
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use chrono::Utc;
+
+    use crate::{
+        backend::backend,
+        storage::{storage_dir, use_test_storage_dir},
+        Archive, ArchiveEntry, EncryptionKey, OnDisk, OnDiskEncrypted, OnDiskMap, StoreError,
+    };
+
+    static LEVEL: OnDisk<u32> = OnDisk::new("level").namespace("archive_test");
+    static SECRET: OnDiskEncrypted<String> = OnDiskEncrypted::new("secret").namespace("archive_test");
+    static SCORES: OnDiskMap<String, u32> = OnDiskMap::namespaced("archive_test", "scores");
+
+    #[test]
+    fn archive() -> Result<()> {
+        use_test_storage_dir();
+        let key = EncryptionKey::new([1; 32]);
+
+        LEVEL.set(5_u32);
+        SECRET.set("password", &key);
+        SCORES.clear();
+        SCORES.insert("player".to_string(), 10_u32);
+
+        let data = Archive::export_namespace("archive_test")?.to_bytes()?;
+        let archive = Archive::from_bytes(&data)?;
+
+        assert_eq!(archive.namespace.as_deref(), Some("archive_test"));
+
+        let keys: Vec<_> = archive.entries.iter().map(|entry| entry.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            ["archive_test/level", "archive_test/scores", "archive_test/secret"]
+        );
+
+        let secret = archive.entries.iter().find(|entry| entry.key == "archive_test/secret").unwrap();
+        assert!(secret.encrypted);
+        assert!(!String::from_utf8_lossy(&secret.data).contains("password"));
+
+        LEVEL.set(20_u32);
+        SECRET.set("other", &key);
+        SCORES.insert("player".to_string(), 50_u32);
+        SCORES.insert("other".to_string(), 1_u32);
+
+        let backup = storage_dir()?.join("archive_test").join("level.1.bak");
+        backend().write(&backup, b"backup")?;
+
+        archive.restore()?;
+
+        assert_eq!(backend().read(&backup)?, b"backup");
+        assert_eq!(LEVEL.get(), 5);
+        assert_eq!(SECRET.get(&key), "password");
+        assert_eq!(SCORES.get(&"player".to_string()), Some(10));
+        assert_eq!(SCORES.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_archive() {
+        use_test_storage_dir();
+        assert!(matches!(
+            Archive::from_bytes(b"broken"),
+            Err(StoreError::Parse(_))
+        ));
+        assert!(matches!(
+            Archive::from_bytes(b"TEA\x05"),
+            Err(StoreError::Parse(_))
+        ));
+
+        let mut archive = Archive::from_bytes(
+            &Archive {
+                created_at: Default::default(),
+                namespace:  None,
+                entries:    vec![],
+            }
+            .to_bytes()
+            .unwrap(),
+        )
+        .unwrap();
+
+        archive.entries.push(ArchiveEntry {
+            key:       "../outside".into(),
+            encrypted: false,
+            data:      vec![],
+        });
+
+        assert!(archive.restore().is_err());
+    }
+
+    static KEPT: OnDisk<u32> = OnDisk::new("kept").namespace("archive_outside_test");
+
+    #[test]
+    fn entries_outside_namespace() {
+        use_test_storage_dir();
+        KEPT.set(5_u32);
+
+        for key in [
+            "archive_outside_test/../kept",
+            "/archive_outside_test/kept",
+            "kept",
+            "other/kept",
+            "archive_outside_test",
+            "archive_outside_test/kept.1.bak",
+        ] {
+            let archive = Archive {
+                created_at: Utc::now(),
+                namespace:  Some("archive_outside_test".into()),
+                entries:    vec![ArchiveEntry {
+                    key:       key.into(),
+                    encrypted: false,
+                    data:      b"1".to_vec(),
+                }],
+            };
+
+            assert!(matches!(archive.restore(), Err(StoreError::Parse(_))), "{key}");
+        }
+
+        assert_eq!(KEPT.get(), 5);
+    }
+}
@@ -0,0 +1,264 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard,
+    },
+    time::SystemTime,
+};
+
+use crate::file::write_atomic;
+
+/// File modification time and size.
+pub(crate) type Stamp = Option<(SystemTime, u64)>;
+
+/// Where stored files are kept.
+pub(crate) trait Backend: Send + Sync {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    /// Replaces file contents. Never leaves partially written file behind.
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()>;
+    /// Adds data to the end of file and returns its offset.
+    fn append(&self, path: &Path, data: &[u8]) -> io::Result<u64>;
+    fn read_range(&self, path: &Path, range: Range<u64>) -> io::Result<Vec<u8>>;
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()>;
+    /// Does nothing if file doesn't exist.
+    fn remove(&self, path: &Path) -> io::Result<()>;
+    fn exists(&self, path: &Path) -> bool;
+    /// All files inside of `dir` and its subdirectories. Empty if `dir`
+    /// doesn't exist.
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>>;
+    fn stamp(&self, path: &Path) -> Stamp;
+}
+
+struct Disk;
+
+impl Backend for Disk {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        write_atomic(path, data)
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> io::Result<u64> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        let offset = file.metadata()?.len();
+        file.write_all(data)?;
+        file.sync_data()?;
+        Ok(offset)
+    }
+
+    fn read_range(&self, path: &Path, range: Range<u64>) -> io::Result<Vec<u8>> {
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(range.start))?;
+        let mut data = vec![0; (range.end - range.start) as usize];
+        file.read_exact(&mut data)?;
+        Ok(data)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        if let Some(dir) = to.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::rename(from, to)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::copy(from, to).map(|_| ())
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        match fs::remove_file(path) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err),
+        };
+
+        let mut files = vec![];
+
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                files.extend(self.list(&path)?);
+            } else {
+                files.push(path);
+            }
+        }
+
+        Ok(files)
+    }
+
+    fn stamp(&self, path: &Path) -> Stamp {
+        let meta = fs::metadata(path).ok()?;
+        Some((meta.modified().ok()?, meta.len()))
+    }
+}
+
+type Files = BTreeMap<PathBuf, (Vec<u8>, SystemTime)>;
+
+struct Memory {
+    files: Mutex<Files>,
+}
+
+impl Memory {
+    fn files(&self) -> MutexGuard<Files> {
+        self.files.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn not_found(path: &Path) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not stored in memory", path.display()),
+        )
+    }
+}
+
+impl Backend for Memory {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.files()
+            .get(path)
+            .map(|(data, _)| data.clone())
+            .ok_or_else(|| Self::not_found(path))
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.files().insert(path.into(), (data.into(), SystemTime::now()));
+        Ok(())
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> io::Result<u64> {
+        let mut files = self.files();
+        let (file, time) = files.entry(path.into()).or_insert_with(|| (vec![], SystemTime::now()));
+        let offset = file.len() as u64;
+        file.extend_from_slice(data);
+        *time = SystemTime::now();
+        Ok(offset)
+    }
+
+    fn read_range(&self, path: &Path, range: Range<u64>) -> io::Result<Vec<u8>> {
+        let files = self.files();
+        let (file, _) = files.get(path).ok_or_else(|| Self::not_found(path))?;
+        file.get(range.start as usize..range.end as usize)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "Range is out of file bounds"))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut files = self.files();
+        let file = files.remove(from).ok_or_else(|| Self::not_found(from))?;
+        files.insert(to.into(), file);
+        Ok(())
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut files = self.files();
+        let file = files.get(from).cloned().ok_or_else(|| Self::not_found(from))?;
+        files.insert(to.into(), file);
+        Ok(())
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        self.files().remove(path);
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files().contains_key(path)
+    }
+
+    fn list(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        Ok(self.files().keys().filter(|path| path.starts_with(dir)).cloned().collect())
+    }
+
+    fn stamp(&self, path: &Path) -> Stamp {
+        self.files().get(path).map(|(data, time)| (*time, data.len() as u64))
+    }
+}
+
+static DISK: Disk = Disk;
+static MEMORY: Memory = Memory {
+    files: Mutex::new(BTreeMap::new()),
+};
+static IN_MEMORY: AtomicBool = AtomicBool::new(false);
+
+/// Keeps all stored values in memory instead of files. Meant for tests so
+/// they don't touch user's home directory. Values stored on disk before the
+/// call are not visible.
+pub fn use_memory_storage() {
+    IN_MEMORY.store(true, Ordering::Relaxed);
+}
+
+pub(crate) fn backend() -> &'static dyn Backend {
+    if IN_MEMORY.load(Ordering::Relaxed) {
+        &MEMORY
+    } else {
+        &DISK
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::backend::{Backend, Memory};
+
+    #[test]
+    fn memory() {
+        let memory = Memory {
+            files: Default::default(),
+        };
+
+        let path = Path::new("memory_test");
+        let copy = Path::new("memory_test_copy");
+        let renamed = Path::new("memory_test_renamed");
+
+        assert!(!memory.exists(path));
+        assert!(memory.read(path).is_err());
+        assert_eq!(memory.stamp(path), None);
+
+        memory.write(path, b"data").unwrap();
+        assert_eq!(memory.read(path).unwrap(), b"data");
+        assert_eq!(memory.stamp(path).unwrap().1, 4);
+
+        memory.copy(path, copy).unwrap();
+        memory.rename(path, renamed).unwrap();
+
+        assert!(!memory.exists(path));
+        assert_eq!(memory.read(copy).unwrap(), b"data");
+        assert_eq!(memory.read(renamed).unwrap(), b"data");
+        assert!(memory.rename(path, copy).is_err());
+
+        memory.remove(copy).unwrap();
+        memory.remove(copy).unwrap();
+        assert!(!memory.exists(copy));
+        assert_eq!(memory.list(Path::new("")).unwrap(), [renamed]);
+
+        assert_eq!(memory.append(path, b"ab").unwrap(), 0);
+        assert_eq!(memory.append(path, b"cd").unwrap(), 2);
+        assert_eq!(memory.read_range(path, 1..3).unwrap(), b"bc");
+        assert!(memory.read_range(path, 3..5).is_err());
+    }
+}
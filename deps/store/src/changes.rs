@@ -0,0 +1,195 @@
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    mem,
+    sync::{Mutex, MutexGuard, Once},
+    thread,
+    time::Duration,
+};
+
+use dispatch::on_main;
+use log::error;
+use refs::{is_main_thread, Weak};
+
+use crate::{
+    backend::{backend, Stamp},
+    storage::{Storage, StorageId},
+    StoreResult,
+};
+
+/// What caused stored value to change.
+pub(crate) enum Change<'a> {
+    /// Value was set on main thread.
+    Set(&'a dyn Any),
+    /// Value was set on another thread and has to be read again.
+    Reload,
+    /// File was modified outside of the app.
+    External,
+}
+
+type Action = Box<dyn FnMut(&Change) + Send>;
+
+struct Subscriber {
+    storage:    Storage,
+    subscriber: Weak,
+    action:     Action,
+}
+
+static SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+static STAMPS: Mutex<BTreeMap<StorageId, Stamp>> = Mutex::new(BTreeMap::new());
+static WRITING: Mutex<BTreeMap<StorageId, usize>> = Mutex::new(BTreeMap::new());
+static WATCHER: Once = Once::new();
+
+fn subscribers() -> MutexGuard<'static, Vec<Subscriber>> {
+    SUBSCRIBERS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn stamps() -> MutexGuard<'static, BTreeMap<StorageId, Stamp>> {
+    STAMPS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Number of own writes in progress for each storage.
+fn writing() -> MutexGuard<'static, BTreeMap<StorageId, usize>> {
+    WRITING.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// `reload` is used when changed value is not available on main thread.
+pub(crate) fn subscribe<T: Clone + 'static, U: ?Sized>(
+    storage: Storage,
+    subscriber: Weak<U>,
+    reload: impl Fn(&Change) -> StoreResult<T> + Send + 'static,
+    mut action: impl FnMut(T) + Send + 'static,
+) {
+    let mut subs = subscribers();
+    subs.retain(|sub| sub.subscriber.is_ok());
+
+    assert!(
+        !subs
+            .iter()
+            .any(|sub| sub.storage.id() == storage.id() && sub.subscriber.addr() == subscriber.addr()),
+        "This object is already subscribed to {}",
+        storage.name
+    );
+
+    subs.push(Subscriber {
+        storage,
+        subscriber: subscriber.erase(),
+        action: Box::new(move |change| {
+            if let Change::Set(value) = change
+                && let Some(value) = value.downcast_ref::<T>()
+            {
+                return action(value.clone());
+            }
+
+            match reload(change) {
+                Ok(value) => action(value),
+                Err(err) => error!("Failed to get {} after change: {err}", storage.name),
+            }
+        }),
+    });
+}
+
+pub(crate) fn notify(storage: Storage, value: &dyn Any) {
+    if !subscribers().iter().any(|sub| sub.storage.id() == storage.id()) {
+        return;
+    }
+
+    if is_main_thread() {
+        trigger(storage, &Change::Set(value));
+    } else {
+        on_main(move || trigger(storage, &Change::Reload));
+    }
+}
+
+/// Subscribers are taken out of the list while called so they can set values
+/// and subscribe. Changes made from inside of an action are not reported again.
+fn trigger(storage: Storage, change: &Change) {
+    let mut subs = mem::take(&mut *subscribers());
+    subs.retain(|sub| sub.subscriber.is_ok());
+
+    for sub in subs.iter_mut().filter(|sub| sub.storage.id() == storage.id()) {
+        (sub.action)(change);
+    }
+
+    let mut current = subscribers();
+    subs.append(&mut current);
+    *current = subs;
+}
+
+fn subscribed_storages() -> Vec<Storage> {
+    let mut storages: Vec<_> = subscribers().iter().map(|sub| sub.storage).collect();
+    storages.sort_unstable_by_key(Storage::id);
+    storages.dedup_by_key(|storage| storage.id());
+    storages
+}
+
+/// Whole storage was replaced so every subscriber reads its value again.
+pub(crate) fn notify_all() {
+    for storage in subscribed_storages() {
+        own_write(storage, || ());
+        on_main(move || trigger(storage, &Change::External));
+    }
+}
+
+fn stamp(storage: &Storage) -> Stamp {
+    backend().stamp(&storage.path().ok()?)
+}
+
+/// Own writes are not reported as external changes. Watcher skips files while
+/// they are written so it can't see own write before its stamp is updated.
+pub(crate) fn own_write<T>(storage: Storage, write: impl FnOnce() -> T) -> T {
+    if !WATCHER.is_completed() {
+        return write();
+    }
+
+    *writing().entry(storage.id()).or_default() += 1;
+    let result = write();
+    let stamp = stamp(&storage);
+
+    stamps().insert(storage.id(), stamp);
+
+    let mut writing = writing();
+    if let Some(count) = writing.get_mut(&storage.id()) {
+        *count -= 1;
+        if *count == 0 {
+            writing.remove(&storage.id());
+        }
+    }
+
+    result
+}
+
+/// Starts checking files of values with subscribers every `period` and
+/// notifies subscribers when a file is modified outside of the app.
+/// Useful for hand edited debug settings.
+pub fn watch_external_changes(period: Duration) {
+    WATCHER.call_once(|| {
+        thread::Builder::new()
+            .name("store watcher".into())
+            .spawn(move || loop {
+                check_external_changes();
+                thread::sleep(period);
+            })
+            .expect("Failed to spawn store watcher thread");
+    });
+}
+
+pub(crate) fn check_external_changes() {
+    let storages = subscribed_storages();
+    let mut stamps = stamps();
+
+    for storage in storages {
+        // Checked after the stamp is taken. Own write can't finish while stamps
+        // are locked so the file isn't reported if it was written meanwhile.
+        let stamp = stamp(&storage);
+        if writing().contains_key(&storage.id()) {
+            continue;
+        }
+
+        if let Some(previous) = stamps.insert(storage.id(), stamp)
+            && previous != stamp
+        {
+            on_main(move || trigger(storage, &Change::External));
+        }
+    }
+}
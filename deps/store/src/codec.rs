@@ -0,0 +1,137 @@
+use std::io::{Read, Write};
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    migration::{current_version, is_versioned, migrate, split_version, with_version, Migration},
+    storage::Loaded,
+    Storable, StoreError, StoreResult,
+};
+
+/// Converts stored json values to file contents and back.
+pub trait Codec: Send + Sync {
+    fn encode(&self, value: &Value) -> StoreResult<Vec<u8>>;
+    fn decode(&self, data: &[u8]) -> StoreResult<Value>;
+}
+
+/// Pretty printed json. Default for all stored values.
+pub struct Json;
+
+impl Codec for Json {
+    fn encode(&self, value: &Value) -> StoreResult<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(value)?)
+    }
+
+    fn decode(&self, data: &[u8]) -> StoreResult<Value> {
+        Ok(serde_json::from_slice(data)?)
+    }
+}
+
+/// Compact binary format.
+pub struct MessagePack;
+
+impl Codec for MessagePack {
+    fn encode(&self, value: &Value) -> StoreResult<Vec<u8>> {
+        rmp_serde::to_vec(value).map_err(|err| StoreError::Parse(err.into()))
+    }
+
+    fn decode(&self, data: &[u8]) -> StoreResult<Value> {
+        rmp_serde::from_slice(data).map_err(|err| StoreError::Parse(err.into()))
+    }
+}
+
+/// Deflate compression on top of another codec.
+pub struct Compressed<C: Codec>(pub C);
+
+impl<C: Codec> Codec for Compressed<C> {
+    fn encode(&self, value: &Value) -> StoreResult<Vec<u8>> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&self.0.encode(value)?)?;
+        Ok(encoder.finish()?)
+    }
+
+    fn decode(&self, data: &[u8]) -> StoreResult<Value> {
+        let mut decoded = Vec::new();
+        DeflateDecoder::new(data)
+            .read_to_end(&mut decoded)
+            .map_err(|err| StoreError::Parse(err.into()))?;
+        self.0.decode(&decoded)
+    }
+}
+
+pub(crate) fn encode(value: &impl Serialize, version: u32, codec: &dyn Codec) -> StoreResult<Vec<u8>> {
+    codec.encode(&with_version(version, serde_json::to_value(value)?))
+}
+
+/// Everything stored before codecs were introduced is json. Such data is
+/// accepted by any codec and has to be rewritten. Data written by a codec
+/// always has version header so anything without it is treated as json.
+pub(crate) fn decode<T: Storable>(
+    data: &[u8],
+    codec: &dyn Codec,
+    migrations: &[Migration],
+) -> StoreResult<Loaded<T>> {
+    let (json, fallback) = match codec.decode(data) {
+        Ok(json) if is_versioned(&json) => (json, false),
+        decoded => match Json.decode(data) {
+            Ok(json) => (json, true),
+            Err(_) => (decoded?, false),
+        },
+    };
+
+    let (version, value) = split_version(json);
+
+    if version == current_version(migrations) {
+        return Ok((serde_json::from_value(value)?, fallback));
+    }
+
+    let value = migrate(value, version, migrations)?;
+    let value = serde_json::from_value(value).map_err(|err| StoreError::Migration {
+        version,
+        reason: err.to_string(),
+    })?;
+
+    Ok((value, true))
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Value};
+
+    use crate::{Codec, Compressed, Json, MessagePack};
+
+    fn data() -> Value {
+        let levels: Vec<_> = (0..100)
+            .map(|index| json!({ "level": index, "stars": 3, "completed": true, "name": "Level" }))
+            .collect();
+        json!({ "player": "Sokol", "levels": levels })
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = data();
+
+        let codecs: [&dyn Codec; 4] = [&Json, &MessagePack, &Compressed(Json), &Compressed(MessagePack)];
+
+        for codec in codecs {
+            assert_eq!(codec.decode(&codec.encode(&data).unwrap()).unwrap(), data);
+        }
+
+        assert!(MessagePack.decode(b"\xc1").is_err());
+        assert!(Compressed(Json).decode(b"{}").is_err());
+    }
+
+    #[test]
+    fn size() {
+        let data = data();
+
+        let json = Json.encode(&data).unwrap().len();
+        let pack = MessagePack.encode(&data).unwrap().len();
+        let compressed = Compressed(MessagePack).encode(&data).unwrap().len();
+
+        assert!(pack < json);
+        assert!(compressed < pack);
+    }
+}
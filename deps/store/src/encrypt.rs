@@ -1,61 +1,275 @@
+use std::sync::{Mutex, MutexGuard};
+
 use aes_gcm::{
-    aead::{Aead, KeyInit, Nonce},
+    aead::{Aead, AeadCore, KeyInit, Nonce, OsRng},
     Aes256Gcm,
 };
+use pbkdf2::pbkdf2_hmac;
+use sha2::{Digest, Sha256};
+
+use crate::{StoreError, StoreResult};
 
-const AES_KEY_SIZE: usize = 32;
+pub const KEY_SIZE: usize = 32;
 const NONCE_SIZE: usize = 12;
-pub const KEY_SIZE: usize = AES_KEY_SIZE + NONCE_SIZE;
+pub const LEGACY_KEY_SIZE: usize = KEY_SIZE + NONCE_SIZE;
+
+/// Encrypted data layout: `MAGIC | FORMAT_VERSION | key id | nonce |
+/// ciphertext`. Version 1 data has no key id. Data without the header was
+/// encrypted with a nonce stored in the legacy key.
+const MAGIC: &[u8] = b"TEE";
+const FORMAT_VERSION: u8 = 2;
+const NO_KEY_ID_VERSION: u8 = 1;
+const KEY_ID_SIZE: usize = 4;
+
+const PBKDF2_ROUNDS: u32 = 600_000;
+
+static PREVIOUS_KEYS: Mutex<Vec<EncryptionKey>> = Mutex::new(Vec::new());
+
+#[derive(Clone)]
+pub struct EncryptionKey {
+    key:          [u8; KEY_SIZE],
+    legacy_nonce: Option<[u8; NONCE_SIZE]>,
+}
+
+impl EncryptionKey {
+    pub const fn new(key: [u8; KEY_SIZE]) -> Self {
+        Self {
+            key,
+            legacy_nonce: None,
+        }
+    }
+
+    /// Key with a fixed nonce packed after key bytes. Needed only to read data
+    /// stored before random nonces were introduced. New data is always
+    /// encrypted with a random nonce.
+    pub const fn legacy(bytes: [u8; LEGACY_KEY_SIZE]) -> Self {
+        let mut key = [0; KEY_SIZE];
+        let mut nonce = [0; NONCE_SIZE];
+
+        let mut i = 0;
+        while i < LEGACY_KEY_SIZE {
+            if i < KEY_SIZE {
+                key[i] = bytes[i];
+            } else {
+                nonce[i - KEY_SIZE] = bytes[i];
+            }
+            i += 1;
+        }
+
+        Self {
+            key,
+            legacy_nonce: Some(nonce),
+        }
+    }
+
+    /// Derives key with PBKDF2-HMAC-SHA256. Salt should be unique per game and
+    /// at least 16 bytes long.
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> Self {
+        Self::derive(passphrase, salt, PBKDF2_ROUNDS)
+    }
+
+    /// [`EncryptionKey::from_passphrase`] with custom number of rounds. Tests
+    /// are built without optimizations so most of them use fewer rounds.
+    pub(crate) fn derive(passphrase: &str, salt: &[u8], rounds: u32) -> Self {
+        let mut key = [0; KEY_SIZE];
+        pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, rounds, &mut key);
+        Self::new(key)
+    }
+
+    pub fn random() -> Self {
+        Self::new(Aes256Gcm::generate_key(&mut OsRng).into())
+    }
+
+    /// Stored in encrypted data header to find the key it was encrypted
+    /// with. Derived from key bytes so it doesn't have to be managed
+    /// manually.
+    pub fn id(&self) -> u32 {
+        let hash = Sha256::digest(self.key);
+        u32::from_le_bytes(hash[..KEY_ID_SIZE].try_into().expect("Hash is longer than key id"))
+    }
 
-pub type EncryptionKey = [u8; KEY_SIZE];
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(&self.key.into())
+    }
+}
 
-fn to_key_nonce(key: &EncryptionKey) -> (aes_gcm::Key<Aes256Gcm>, Nonce<Aes256Gcm>) {
-    let nonce = &key[AES_KEY_SIZE..];
-    let key = &key[..AES_KEY_SIZE];
+fn previous_keys() -> MutexGuard<'static, Vec<EncryptionKey>> {
+    PREVIOUS_KEYS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
 
-    (
-        *aes_gcm::Key::<Aes256Gcm>::from_slice(key),
-        *Nonce::<Aes256Gcm>::from_slice(nonce),
-    )
+/// Key that encrypted data before current key was introduced. Data that
+/// fails to decrypt with current key is decrypted with previous keys and
+/// encrypted again with current key when it is read.
+pub fn add_previous_key(key: EncryptionKey) {
+    let mut keys = previous_keys();
+    if !keys
+        .iter()
+        .any(|previous| previous.key == key.key && previous.legacy_nonce == key.legacy_nonce)
+    {
+        keys.push(key);
+    }
 }
 
 pub fn encrypt(data: &[u8], key: &EncryptionKey) -> Vec<u8> {
-    let (key, nonce) = to_key_nonce(key);
-    let cipher = Aes256Gcm::new(&key);
-    cipher.encrypt(&nonce, data).unwrap()
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let encrypted = key.cipher().encrypt(&nonce, data).expect("Failed to encrypt data");
+
+    [
+        MAGIC,
+        &[FORMAT_VERSION],
+        &key.id().to_le_bytes(),
+        nonce.as_slice(),
+        &encrypted,
+    ]
+    .concat()
+}
+
+/// Legacy encrypted data has no header and is not recognized.
+pub(crate) fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+pub fn decrypt(data: &[u8], key: &EncryptionKey) -> StoreResult<Vec<u8>> {
+    let cipher = key.cipher();
+
+    if let Some(data) = data.strip_prefix(MAGIC)
+        && let Some((&version, data)) = data.split_first()
+    {
+        let data = match version {
+            FORMAT_VERSION => match data.split_at_checked(KEY_ID_SIZE) {
+                Some((id, data)) if id == key.id().to_le_bytes() => Some(data),
+                _ => None,
+            },
+            NO_KEY_ID_VERSION => Some(data),
+            _ => None,
+        };
+
+        if let Some((nonce, encrypted)) = data.and_then(|data| data.split_at_checked(NONCE_SIZE))
+            && let Ok(decrypted) = cipher.decrypt(Nonce::<Aes256Gcm>::from_slice(nonce), encrypted)
+        {
+            return Ok(decrypted);
+        }
+    }
+
+    let Some(nonce) = key.legacy_nonce else {
+        return Err(StoreError::Decrypt);
+    };
+
+    cipher.decrypt(&nonce.into(), data).map_err(|_| StoreError::Decrypt)
 }
 
-pub fn decrypt(data: &[u8], key: &EncryptionKey) -> Vec<u8> {
-    let (key, nonce) = to_key_nonce(key);
-    let cipher = Aes256Gcm::new(&key);
-    cipher.decrypt(&nonce, data).unwrap()
+/// Decrypts with current key or one of keys added with
+/// [`add_previous_key`]. Returns whether data has to be encrypted again
+/// because it was encrypted with a previous key or in older format.
+pub(crate) fn decrypt_rotated(data: &[u8], key: &EncryptionKey) -> StoreResult<(Vec<u8>, bool)> {
+    if let Ok(decrypted) = decrypt(data, key) {
+        let current = [MAGIC, &[FORMAT_VERSION]].concat();
+        return Ok((decrypted, !data.starts_with(&current)));
+    }
+
+    previous_keys()
+        .iter()
+        .find_map(|previous| decrypt(data, previous).ok())
+        .map(|decrypted| (decrypted, true))
+        .ok_or(StoreError::Decrypt)
 }
 
 #[cfg(test)]
 mod test {
 
+    use aes_gcm::{
+        aead::{Aead, KeyInit},
+        Aes256Gcm,
+    };
     use rand::{thread_rng, RngCore};
 
     use crate::{
-        encrypt::{decrypt, encrypt, KEY_SIZE},
+        encrypt::{add_previous_key, decrypt, decrypt_rotated, encrypt, LEGACY_KEY_SIZE},
         EncryptionKey,
     };
 
+    const DATA: &[u8] = b"SOKOLLL!! fjdsa fjasd;k flkdsa hfjklsda lfdkkadshksalkjaskjd jljljsdslkjsksj";
+
     #[test]
     fn test() {
-        let mut key: EncryptionKey = [0; KEY_SIZE];
-        thread_rng().fill_bytes(&mut key);
+        let key = EncryptionKey::random();
+
+        let encrypted = encrypt(DATA, &key);
+
+        let decrypted = decrypt(&encrypted, &key).unwrap();
+
+        assert_eq!(decrypted, DATA);
+
+        assert_ne!(encrypted, encrypt(DATA, &key));
+        assert!(decrypt(&encrypted, &EncryptionKey::random()).is_err());
+    }
+
+    #[test]
+    fn legacy() {
+        let mut bytes = [0; LEGACY_KEY_SIZE];
+        thread_rng().fill_bytes(&mut bytes);
+
+        let cipher = Aes256Gcm::new_from_slice(&bytes[..32]).unwrap();
+        let encrypted = cipher.encrypt(bytes[32..].into(), DATA).unwrap();
 
-        let encrypted = encrypt(
-            b"SOKOLLL!! fjdsa fjasd;k flkdsa hfjklsda lfdkkadshksalkjaskjd jljljsdslkjsksj",
-            &key,
+        let key = EncryptionKey::legacy(bytes);
+
+        assert_eq!(decrypt(&encrypted, &key).unwrap(), DATA);
+        assert_eq!(decrypt(&encrypt(DATA, &key), &key).unwrap(), DATA);
+        assert!(decrypt(&encrypted, &EncryptionKey::new(key.key)).is_err());
+    }
+
+    #[test]
+    fn passphrase() {
+        let key = EncryptionKey::derive("sokol", b"test_engine_salt", 1_000);
+        let same = EncryptionKey::derive("sokol", b"test_engine_salt", 1_000);
+        let other_salt = EncryptionKey::derive("sokol", b"other_engine_salt", 1_000);
+        let other_rounds = EncryptionKey::derive("sokol", b"test_engine_salt", 1_001);
+
+        assert_eq!(key.key, same.key);
+        assert_ne!(key.key, other_salt.key);
+        assert_ne!(key.key, other_rounds.key);
+        assert_eq!(decrypt(&encrypt(DATA, &key), &same).unwrap(), DATA);
+    }
+
+    /// Keys of existing saves must never change. Expected bytes are from an
+    /// independent PBKDF2-HMAC-SHA256 implementation with 600 000 rounds.
+    #[test]
+    fn passphrase_key_is_stable() {
+        let key = EncryptionKey::from_passphrase("sokol", b"test_engine_salt");
+
+        assert_eq!(
+            key.key,
+            [
+                22, 55, 141, 40, 224, 205, 85, 138, 136, 87, 40, 21, 37, 178, 186, 181, 246, 11, 174, 162, 1,
+                73, 181, 246, 245, 252, 95, 249, 142, 157, 218, 190
+            ]
         );
+    }
+
+    #[test]
+    fn rotation() {
+        let old = EncryptionKey::random();
+        let current = EncryptionKey::random();
+
+        let encrypted = encrypt(DATA, &old);
+        assert_eq!(encrypted[4..8], old.id().to_le_bytes());
+        assert_ne!(old.id(), current.id());
 
-        let decrypted = decrypt(&encrypted, &key);
+        assert!(decrypt(&encrypted, &current).is_err());
+        assert!(decrypt_rotated(&encrypted, &current).is_err());
+
+        add_previous_key(old.clone());
 
         assert_eq!(
-            decrypted,
-            b"SOKOLLL!! fjdsa fjasd;k flkdsa hfjklsda lfdkkadshksalkjaskjd jljljsdslkjsksj"
+            decrypt_rotated(&encrypted, &current).unwrap(),
+            (DATA.to_vec(), true)
         );
+        assert_eq!(decrypt_rotated(&encrypted, &old).unwrap(), (DATA.to_vec(), false));
+
+        let mut version_1 = encrypted.clone();
+        version_1.drain(3..8);
+        version_1.insert(3, 1);
+        assert_eq!(decrypt_rotated(&version_1, &old).unwrap(), (DATA.to_vec(), true));
     }
 }
@@ -0,0 +1,68 @@
+use std::{
+    error::Error,
+    fmt::{Display, Formatter},
+    io,
+};
+
+pub type StoreResult<T> = Result<T, StoreError>;
+
+#[derive(Debug)]
+pub enum StoreError {
+    Io(io::Error),
+    Parse(Box<dyn Error + Send + Sync>),
+    Decrypt,
+    Migration { version: u32, reason: String },
+    OutOfBounds { index: usize, len: usize },
+}
+
+impl Display for StoreError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Storage io error: {err}"),
+            Self::Parse(err) => write!(f, "Failed to parse stored data: {err}"),
+            Self::Decrypt => write!(f, "Failed to decrypt stored data"),
+            Self::Migration { version, reason } => {
+                write!(
+                    f,
+                    "Failed to migrate stored data from version {version}: {reason}"
+                )
+            }
+            Self::OutOfBounds { index, len } => {
+                write!(f, "Index {index} is out of bounds of length {len}")
+            }
+        }
+    }
+}
+
+impl Error for StoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(err) => Some(err.as_ref()),
+            Self::Decrypt | Self::Migration { .. } | Self::OutOfBounds { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for StoreError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for StoreError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Parse(err.into())
+    }
+}
+
+/// What to do with stored data that failed to load.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Recovery {
+    /// Overwrite broken data with default value.
+    Reset,
+    /// Move broken data next to the original file and start from default value.
+    Backup,
+    /// Return the error to the caller.
+    Propagate,
+}
@@ -0,0 +1,72 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::backend::Backend;
+
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path: OsString = path.into();
+    path.push(suffix);
+    path.into()
+}
+
+/// Writes to a temporary file and renames it over the original so a crash
+/// mid-write never leaves a truncated file behind.
+pub(crate) fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let tmp = with_suffix(
+        path,
+        &format!(".{}.tmp", TMP_COUNTER.fetch_add(1, Ordering::Relaxed)),
+    );
+
+    let write = || -> io::Result<()> {
+        let mut file = File::create(&tmp)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    };
+
+    if let Err(err) = write() {
+        _ = fs::remove_file(&tmp);
+        return Err(err);
+    }
+
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        File::open(dir)?.sync_all()?;
+    }
+
+    Ok(())
+}
+
+/// `<path>.1.bak` is the most recent backup.
+pub(crate) fn backup_file(path: &Path, index: usize) -> PathBuf {
+    with_suffix(path, &format!(".{index}.bak"))
+}
+
+/// Shifts existing backups by one and writes `previous` data to the first
+/// backup. The oldest backup is dropped.
+pub(crate) fn rotate_backups(
+    backend: &dyn Backend,
+    path: &Path,
+    previous: &[u8],
+    count: usize,
+) -> io::Result<()> {
+    if count == 0 {
+        return Ok(());
+    }
+
+    for index in (1..count).rev() {
+        let backup = backup_file(path, index);
+        if backend.exists(&backup) {
+            backend.rename(&backup, &backup_file(path, index + 1))?;
+        }
+    }
+
+    backend.write(&backup_file(path, 1), previous)
+}
@@ -0,0 +1,205 @@
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    marker::PhantomData,
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+
+use log::warn;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    backend::{backend, Stamp},
+    storage::{generation, Storage, StorageId},
+    StoreResult,
+};
+
+/// Journal is rewritten when it has at least this many records and most of them
+/// are obsolete.
+const COMPACT_THRESHOLD: usize = 64;
+
+/// Position of a record line in the journal file.
+pub(crate) type Record = Range<u64>;
+
+/// In-memory index of a collection. Keeps positions of live records only so
+/// values are read from disk when needed.
+pub(crate) trait Index: Default + Send + 'static {
+    /// Updates index with a record read from the journal.
+    fn apply(&mut self, line: &[u8], record: Record) -> StoreResult<()>;
+    fn records_mut(&mut self) -> impl Iterator<Item = &mut Record>;
+    fn len(&self) -> usize;
+
+    /// Converts a live record line before it is written by compaction.
+    fn compacted(line: &[u8]) -> StoreResult<Vec<u8>> {
+        Ok(line.to_vec())
+    }
+}
+
+struct State<I> {
+    index:      I,
+    records:    usize,
+    generation: u64,
+    /// Journal file as it was after the last access. Index is loaded again if
+    /// the file was changed outside of the app.
+    stamp:      Stamp,
+}
+
+type Shared = Arc<Mutex<Option<Box<dyn Any + Send>>>>;
+
+/// Collections with the same storage share one index so positions of records
+/// appended by one of them are known to the others.
+static STATES: Mutex<BTreeMap<StorageId, Shared>> = Mutex::new(BTreeMap::new());
+
+/// Append-only file of json lines. Each change of a collection is a single
+/// line so updating one entry doesn't rewrite the whole file. Lines of
+/// overwritten and removed entries are dropped by compaction.
+pub(crate) struct Journal<I> {
+    storage: Storage,
+    _p:      PhantomData<I>,
+}
+
+/// Journal file access while index is locked.
+pub(crate) struct Records<'a> {
+    storage: Storage,
+    count:   &'a mut usize,
+}
+
+impl Records<'_> {
+    pub fn append(&mut self, entry: &impl Serialize) -> StoreResult<Record> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        let start = backend().append(&self.storage.path()?, &line)?;
+        *self.count += 1;
+        Ok(start..start + line.len() as u64 - 1)
+    }
+
+    pub fn read<R: DeserializeOwned>(&self, record: &Record) -> StoreResult<R> {
+        let line = backend().read_range(&self.storage.path()?, record.clone())?;
+        Ok(serde_json::from_slice(&line)?)
+    }
+}
+
+impl<I: Index> Journal<I> {
+    pub const fn new(storage: Storage) -> Self {
+        Self {
+            storage,
+            _p: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.storage.name
+    }
+
+    /// Calls `action` with loaded index. Index and journal file are locked
+    /// until it returns.
+    pub fn with<R>(&self, action: impl FnOnce(&mut I, &mut Records) -> StoreResult<R>) -> StoreResult<R> {
+        self.with_state(|state| {
+            let result = action(
+                &mut state.index,
+                &mut Records {
+                    storage: self.storage,
+                    count:   &mut state.records,
+                },
+            )?;
+
+            if state.records >= COMPACT_THRESHOLD && state.records > state.index.len() * 2 {
+                self.compact_state(state)?;
+            }
+
+            Ok(result)
+        })
+    }
+
+    /// Rewrites journal file leaving only live records.
+    pub fn compact(&self) -> StoreResult<()> {
+        self.with_state(|state| self.compact_state(state))
+    }
+
+    fn with_state<R>(&self, action: impl FnOnce(&mut State<I>) -> StoreResult<R>) -> StoreResult<R> {
+        let shared = self.shared();
+        let mut shared = shared.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let path = self.storage.path()?;
+
+        let loaded = shared
+            .as_ref()
+            .and_then(|state| state.downcast_ref::<State<I>>())
+            .is_some_and(|state| state.generation == generation() && state.stamp == backend().stamp(&path));
+
+        if !loaded {
+            *shared = Some(Box::new(self.load()?));
+        }
+
+        let state = shared
+            .as_mut()
+            .and_then(|state| state.downcast_mut::<State<I>>())
+            .expect("Journal state was loaded above");
+
+        let result = action(state);
+        state.stamp = backend().stamp(&path);
+        result
+    }
+
+    fn shared(&self) -> Shared {
+        let mut states = STATES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        states.entry(self.storage.id()).or_default().clone()
+    }
+
+    fn load(&self) -> StoreResult<State<I>> {
+        let mut state = State {
+            index:      I::default(),
+            records:    0,
+            generation: generation(),
+            stamp:      None,
+        };
+
+        let path = self.storage.path()?;
+
+        if !backend().exists(&path) {
+            return Ok(state);
+        }
+
+        let data = backend().read(&path)?;
+        let mut offset = 0;
+
+        for line in data.split_inclusive(|byte| *byte == b'\n') {
+            let start = offset;
+            offset += line.len() as u64;
+
+            let Some(line) = line.strip_suffix(b"\n") else {
+                warn!("Dropping incomplete last record of {}", self.storage.name);
+                self.compact_state(&mut state)?;
+                break;
+            };
+
+            state.index.apply(line, start..offset - 1)?;
+            state.records += 1;
+        }
+
+        Ok(state)
+    }
+
+    fn compact_state(&self, state: &mut State<I>) -> StoreResult<()> {
+        let path = self.storage.path()?;
+        let old = if backend().exists(&path) {
+            backend().read(&path)?
+        } else {
+            vec![]
+        };
+
+        let mut data = vec![];
+
+        for record in state.index.records_mut() {
+            let start = data.len() as u64;
+            data.extend(I::compacted(&old[record.start as usize..record.end as usize])?);
+            *record = start..data.len() as u64;
+            data.push(b'\n');
+        }
+
+        backend().write(&path, &data)?;
+        state.records = state.index.len();
+
+        Ok(())
+    }
+}
@@ -1,8 +1,37 @@
+#![feature(let_chains)]
+
+mod archive;
+mod backend;
+mod changes;
+mod codec;
 mod encrypt;
+mod error;
+mod file;
+mod journal;
+mod migration;
 mod on_disk;
+mod on_disk_cached;
 mod on_disk_encrypted;
+mod on_disk_map;
+mod on_disk_vec;
+mod save_slots;
 mod storable;
+mod storage;
+mod thumbnail;
 
-pub use encrypt::EncryptionKey;
-pub use on_disk::{executable_name, OnDisk};
+pub use archive::{Archive, ArchiveEntry};
+pub use backend::use_memory_storage;
+pub use changes::watch_external_changes;
+pub use codec::{Codec, Compressed, Json, MessagePack};
+pub use encrypt::{add_previous_key, EncryptionKey};
+pub use error::{Recovery, StoreError, StoreResult};
+pub use migration::Migration;
+pub use on_disk::OnDisk;
+pub use on_disk_cached::{flush_all, Flush, OnDiskCached};
 pub use on_disk_encrypted::OnDiskEncrypted;
+pub use on_disk_map::OnDiskMap;
+pub use on_disk_vec::OnDiskVec;
+pub use save_slots::{SaveSlots, SlotInfo, SlotList};
+pub use storable::Storable;
+pub use storage::{executable_name, set_bundle_id, set_storage_dir, use_te_toml, STORAGE_DIR_VAR};
+pub use thumbnail::Thumbnail;
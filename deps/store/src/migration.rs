@@ -0,0 +1,116 @@
+use serde_json::{Map, Value};
+
+use crate::{StoreError, StoreResult};
+
+const VERSION_KEY: &str = "store_version";
+const VALUE_KEY: &str = "value";
+
+/// Upgrades stored json from one schema version to the next.
+/// Migration at index `n` in `OnDisk` migrations list converts version `n`
+/// data to version `n + 1`.
+pub type Migration = fn(Value) -> serde_json::Result<Value>;
+
+pub(crate) fn current_version(migrations: &[Migration]) -> u32 {
+    u32::try_from(migrations.len()).expect("Too many migrations")
+}
+
+pub(crate) fn with_version(version: u32, value: Value) -> Value {
+    let mut map = Map::new();
+    map.insert(VERSION_KEY.into(), version.into());
+    map.insert(VALUE_KEY.into(), value);
+    Value::Object(map)
+}
+
+fn version_of(json: &Value) -> Option<u32> {
+    if let Value::Object(map) = json
+        && map.len() == 2
+        && map.contains_key(VALUE_KEY)
+    {
+        let version = map.get(VERSION_KEY)?.as_u64()?;
+        return Some(u32::try_from(version).unwrap_or(u32::MAX));
+    }
+    None
+}
+
+pub(crate) fn is_versioned(json: &Value) -> bool {
+    version_of(json).is_some()
+}
+
+/// Files written before versioning was added contain raw value json.
+/// They are treated as version 0.
+pub(crate) fn split_version(mut json: Value) -> (u32, Value) {
+    match version_of(&json) {
+        Some(version) => (version, json[VALUE_KEY].take()),
+        None => (0, json),
+    }
+}
+
+pub(crate) fn migrate(value: Value, from: u32, migrations: &[Migration]) -> StoreResult<Value> {
+    let Some(pending) = migrations.get(from as usize..) else {
+        return Err(StoreError::Migration {
+            version: from,
+            reason:  format!(
+                "stored version is newer than supported version {}",
+                current_version(migrations)
+            ),
+        });
+    };
+
+    pending.iter().zip(from..).try_fold(value, |value, (migration, step)| {
+        migration(value).map_err(|err| StoreError::Migration {
+            version: from,
+            reason:  format!("step {step} -> {}: {err}", step + 1),
+        })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Value};
+
+    use crate::{
+        migration::{migrate, split_version, with_version, Migration},
+        StoreError,
+    };
+
+    fn add_field(mut value: Value) -> serde_json::Result<Value> {
+        value["added"] = true.into();
+        Ok(value)
+    }
+
+    fn rename_field(mut value: Value) -> serde_json::Result<Value> {
+        let map = value.as_object_mut().unwrap();
+        let old = map.remove("old").unwrap_or_default();
+        map.insert("new".into(), old);
+        Ok(value)
+    }
+
+    static MIGRATIONS: &[Migration] = &[add_field, rename_field];
+
+    #[test]
+    fn versioning() {
+        let value = json!({ "a": 5 });
+        assert_eq!(split_version(with_version(3, value.clone())), (3, value.clone()));
+        assert_eq!(split_version(value.clone()), (0, value));
+        assert_eq!(split_version(json!(5)), (0, json!(5)));
+    }
+
+    #[test]
+    fn migrating() {
+        let value = json!({ "old": 10 });
+
+        assert_eq!(
+            migrate(value.clone(), 0, MIGRATIONS).unwrap(),
+            json!({ "new": 10, "added": true })
+        );
+        assert_eq!(
+            migrate(value.clone(), 1, MIGRATIONS).unwrap(),
+            json!({ "new": 10 })
+        );
+        assert_eq!(migrate(value.clone(), 2, MIGRATIONS).unwrap(), value);
+        assert!(matches!(
+            migrate(value, 3, MIGRATIONS),
+            Err(StoreError::Migration { version: 3, .. })
+        ));
+    }
+}
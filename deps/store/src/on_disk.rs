@@ -1,81 +1,155 @@
 use std::{
     fmt::{Debug, Formatter},
-    fs,
     marker::PhantomData,
-    path::PathBuf,
 };
 
-use gm::Platform;
+use refs::Weak;
 
-use crate::storable::Storable;
+use crate::{
+    changes::{notify, subscribe},
+    codec::{decode, encode},
+    migration::{current_version, Migration},
+    storable::Storable,
+    storage::Storage,
+    Codec, Flush, Json, OnDiskCached, Recovery, StoreError, StoreResult,
+};
 
-pub fn executable_name() -> String {
-    std::env::current_exe()
-        .expect("Failed to get std::env::current_exe()")
-        .file_name()
-        .expect("Failed to get executable name")
-        .to_string_lossy()
-        .into()
+pub struct OnDisk<T: Storable> {
+    storage:    Storage,
+    migrations: &'static [Migration],
+    codec:      &'static dyn Codec,
+    _p:         PhantomData<T>,
 }
 
-pub(crate) fn storage_dir() -> PathBuf {
-    let home = if Platform::MOBILE {
-        dirs::document_dir()
-    } else {
-        dirs::home_dir()
+impl<T: Storable> OnDisk<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self::with_migrations(name, &[])
     }
-    .expect("Failed to get home directory");
-
-    format!("{}/.{}", home.display(), executable_name()).into()
-}
-
-fn set_value<T: serde::ser::Serialize>(value: T, key: &str) {
-    let json = serde_json::to_string_pretty(&value).expect("Failed to serialize data");
-    let dir = storage_dir();
-    _ = fs::create_dir_all(&dir);
-    fs::write(dir.join(key), json).expect("Failed to write to file");
-}
-
-fn get_value<T: Storable>(key: &str) -> T {
-    let dir = storage_dir();
-    let path = dir.join(key);
 
-    fs::create_dir_all(&dir).unwrap();
-
-    if !path.exists() {
-        let new = T::default();
-        set_value(&new, key);
-        return new;
+    /// Stored data version equals the number of migrations.
+    /// Data saved with older version is migrated and rewritten on first read.
+    pub const fn with_migrations(name: &'static str, migrations: &'static [Migration]) -> Self {
+        Self {
+            storage: Storage::new(name),
+            migrations,
+            codec: &Json,
+            _p: PhantomData,
+        }
     }
-    let json = fs::read_to_string(path).expect("Failed to read file");
-    serde_json::from_str(&json).expect("Failet to parse json")
-}
 
-pub struct OnDisk<T: Storable> {
-    name: &'static str,
-    _p:   PhantomData<T>,
-}
+    /// Stores value in `<namespace>` subdirectory of storage directory.
+    /// Values with the same name in different namespaces don't conflict.
+    pub const fn namespace(self, namespace: &'static str) -> Self {
+        Self {
+            storage: self.storage.namespace(namespace),
+            ..self
+        }
+    }
 
-impl<T: Storable> OnDisk<T> {
-    pub const fn new(name: &'static str) -> Self {
+    /// Number of previous values kept as `<name>.<n>.bak` files. None by
+    /// default. If stored file is corrupted the most recent readable backup
+    /// is used.
+    pub const fn backups(self, backups: usize) -> Self {
         Self {
-            name,
-            _p: PhantomData,
+            storage: self.storage.backups(backups),
+            ..self
         }
     }
 
+    /// File format of stored value. Data previously stored as json is
+    /// converted on first read.
+    pub const fn codec(self, codec: &'static dyn Codec) -> Self {
+        Self { codec, ..self }
+    }
+
+    /// Panics on storage errors.
     pub fn set(&self, val: impl Into<T>) {
-        let val = val.into();
-        set_value(val, self.name);
+        self.try_set(val)
+            .unwrap_or_else(|err| panic!("Failed to store {}: {err}", self.storage.name));
     }
 
+    /// Panics on storage errors. Data that fails to migrate is backed up and
+    /// reset to default.
     pub fn get(&self) -> T {
-        get_value(self.name)
+        self.get_or_recover(|err| match err {
+            StoreError::Migration { .. } => Recovery::Backup,
+            _ => Recovery::Propagate,
+        })
+        .unwrap_or_else(|err| panic!("Failed to get {}: {err}", self.storage.name))
     }
 
     pub fn reset(&self) {
         self.set(T::default());
     }
+
+    pub fn try_set(&self, val: impl Into<T>) -> StoreResult<()> {
+        let val = val.into();
+        self.write(&val)?;
+        notify(self.storage, &val);
+        Ok(())
+    }
+
+    pub fn try_get(&self) -> StoreResult<T> {
+        match self.storage.read(|data| decode(data, self.codec, self.migrations))? {
+            Some((val, rewrite)) => {
+                if rewrite {
+                    self.write(&val)?;
+                }
+                Ok(val)
+            }
+            None => {
+                let new = T::default();
+                self.write(&new)?;
+                Ok(new)
+            }
+        }
+    }
+
+    pub fn try_reset(&self) -> StoreResult<()> {
+        self.try_set(T::default())
+    }
+
+    /// Calls `hook` if stored data can't be loaded and handles it according to
+    /// returned [`Recovery`].
+    pub fn get_or_recover(&self, hook: impl FnOnce(&StoreError) -> Recovery) -> StoreResult<T> {
+        match self.try_get() {
+            Ok(val) => Ok(val),
+            Err(err) => {
+                self.storage.recover(err, hook)?;
+                self.try_reset()?;
+                Ok(T::default())
+            }
+        }
+    }
+
+    pub fn version(&self) -> u32 {
+        current_version(self.migrations)
+    }
+
+    pub(crate) fn storage(&self) -> Storage {
+        self.storage
+    }
+
+    pub(crate) fn write(&self, val: &T) -> StoreResult<()> {
+        self.storage.write(&encode(val, self.version(), self.codec)?, |data| {
+            decode::<T>(data, self.codec, self.migrations)
+        })
+    }
+}
+
+impl<T: Storable + Clone + Sync> OnDisk<T> {
+    /// Calls `action` on main thread with new value every time it is set.
+    /// Subscription ends when `subscriber` is dropped. Edits made outside of
+    /// the app are reported after
+    /// [`watch_external_changes`](crate::watch_external_changes).
+    pub fn on_change<U: ?Sized>(&'static self, subscriber: Weak<U>, action: impl FnMut(T) + Send + 'static) {
+        subscribe(self.storage, subscriber, |_| self.try_get(), action);
+    }
+
+    /// See [`OnDiskCached`].
+    pub const fn cached(self, flush: Flush) -> OnDiskCached<T> {
+        OnDiskCached::with_inner(self, flush)
+    }
 }
 
 impl<T: Storable + Debug> Debug for OnDisk<T> {
@@ -87,12 +161,27 @@ impl<T: Storable + Debug> Debug for OnDisk<T> {
 #[cfg(test)]
 mod test {
 
+    use std::{
+        fs,
+        sync::{Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+
     use anyhow::Result;
+    use dispatch::invoke_dispatched;
     use fake::{Fake, Faker};
+    use refs::Own;
     use serde::{Deserialize, Serialize};
+    use serde_json::{json, Value};
     use tokio::spawn;
 
-    use crate::{on_disk::executable_name, OnDisk};
+    use crate::{
+        changes::check_external_changes,
+        file::backup_file,
+        storage::{executable_name, storage_dir, use_test_storage_dir},
+        watch_external_changes, Compressed, MessagePack, OnDisk, Recovery, StoreError,
+    };
 
     #[derive(Debug, PartialEq, Default, Serialize, Deserialize, Clone)]
     struct Data {
@@ -108,6 +197,8 @@ mod test {
 
     #[tokio::test]
     async fn stored() -> Result<()> {
+        use_test_storage_dir();
+
         check_send(&STORED);
         check_sync(&STORED);
         check_send(&STORED_STRUCT);
@@ -146,8 +237,243 @@ mod test {
         Ok(())
     }
 
+    #[derive(Debug, PartialEq, Default, Serialize, Deserialize)]
+    struct DataV2 {
+        number: i64,
+        text:   String,
+        flag:   bool,
+    }
+
+    fn rename_string(mut value: Value) -> serde_json::Result<Value> {
+        let string = value["string"].take();
+        value["text"] = string;
+        Ok(value)
+    }
+
+    fn add_flag(mut value: Value) -> serde_json::Result<Value> {
+        value["flag"] = true.into();
+        Ok(value)
+    }
+
+    fn fail(_: Value) -> serde_json::Result<Value> {
+        Err(serde::de::Error::custom("broken"))
+    }
+
+    static MIGRATED: OnDisk<DataV2> =
+        OnDisk::with_migrations("migrated_struct_test", &[rename_string, add_flag]);
+    static FAILED: OnDisk<DataV2> = OnDisk::with_migrations("failed_migration_test", &[fail]);
+
+    #[test]
+    fn migrations() -> Result<()> {
+        use_test_storage_dir();
+        let path = storage_dir()?.join("migrated_struct_test");
+        fs::create_dir_all(storage_dir()?)?;
+        fs::write(&path, json!({ "number": 5, "string": "old" }).to_string())?;
+
+        let expected = DataV2 {
+            number: 5,
+            text:   "old".into(),
+            flag:   true,
+        };
+
+        assert_eq!(MIGRATED.version(), 2);
+        assert_eq!(MIGRATED.get(), expected);
+
+        let stored: Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        assert_eq!(stored["store_version"], 2);
+        assert_eq!(MIGRATED.get(), expected);
+
+        let path = storage_dir()?.join("failed_migration_test");
+        let backup = storage_dir()?.join("failed_migration_test.v0");
+        _ = fs::remove_file(&backup);
+        fs::write(&path, json!({ "number": 5 }).to_string())?;
+
+        assert_eq!(FAILED.get(), DataV2::default());
+        assert!(backup.exists());
+
+        fs::write(&path, json!({ "store_version": 10, "value": {} }).to_string())?;
+        assert_eq!(FAILED.get(), DataV2::default());
+        assert!(storage_dir()?.join("failed_migration_test.v10").exists());
+
+        Ok(())
+    }
+
+    static BROKEN: OnDisk<Data> = OnDisk::new("broken_struct_test").backups(0);
+
+    #[test]
+    fn recovery() -> Result<()> {
+        use_test_storage_dir();
+        let path = storage_dir()?.join("broken_struct_test");
+        let backup = storage_dir()?.join("broken_struct_test.broken");
+        fs::create_dir_all(storage_dir()?)?;
+        _ = fs::remove_file(&backup);
+
+        fs::write(&path, "{ broken")?;
+        assert!(matches!(BROKEN.try_get(), Err(StoreError::Parse(_))));
+        assert!(matches!(
+            BROKEN.get_or_recover(|_| Recovery::Propagate),
+            Err(StoreError::Parse(_))
+        ));
+
+        assert_eq!(BROKEN.get_or_recover(|_| Recovery::Reset)?, Data::default());
+        assert!(!backup.exists());
+
+        fs::write(&path, "{ broken")?;
+        assert_eq!(BROKEN.get_or_recover(|_| Recovery::Backup)?, Data::default());
+        assert_eq!(fs::read_to_string(&backup)?, "{ broken");
+        assert_eq!(BROKEN.try_get()?, Data::default());
+
+        Ok(())
+    }
+
+    static BACKED_UP: OnDisk<i32> = OnDisk::new("backed_up_i32_test").backups(2);
+
+    #[test]
+    fn backups() -> Result<()> {
+        use_test_storage_dir();
+        let path = storage_dir()?.join("backed_up_i32_test");
+        let first = backup_file(&path, 1);
+        let second = backup_file(&path, 2);
+
+        for file in [&path, &first, &second] {
+            _ = fs::remove_file(file);
+        }
+
+        BACKED_UP.set(1);
+        assert!(!first.exists());
+
+        BACKED_UP.set(2);
+        BACKED_UP.set(3);
+        BACKED_UP.set(4);
+        assert!(!backup_file(&path, 3).exists());
+
+        fs::write(&path, "{ broken")?;
+        assert_eq!(BACKED_UP.get(), 3);
+        assert_eq!(BACKED_UP.get(), 3);
+        assert!(fs::read_to_string(&path)?.contains('3'));
+
+        fs::write(&path, "{ broken")?;
+        fs::write(&first, "")?;
+        assert_eq!(BACKED_UP.get(), 2);
+
+        // Corrupted value doesn't replace a good backup.
+        BACKED_UP.set(6);
+        BACKED_UP.set(7);
+        fs::write(&path, "{ broken")?;
+        BACKED_UP.set(8);
+        assert!(fs::read_to_string(&first)?.contains('6'));
+        assert_eq!(BACKED_UP.get(), 8);
+
+        for file in [&path, &first, &second] {
+            fs::write(file, "{ broken")?;
+        }
+        assert!(matches!(BACKED_UP.try_get(), Err(StoreError::Parse(_))));
+
+        let tmp_files = fs::read_dir(storage_dir()?)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with("backed_up_i32_test") && name.ends_with(".tmp"))
+            .count();
+        assert_eq!(tmp_files, 0);
+
+        Ok(())
+    }
+
+    static PACKED: OnDisk<Vec<Data>> =
+        OnDisk::new("packed_struct_test").codec(&Compressed(MessagePack)).backups(0);
+
+    #[test]
+    fn codecs() -> Result<()> {
+        use_test_storage_dir();
+        let path = storage_dir()?.join("packed_struct_test");
+        fs::create_dir_all(storage_dir()?)?;
+
+        let data: Vec<_> = (0..100)
+            .map(|number| Data {
+                number,
+                string: "Helloyyyy".to_string(),
+            })
+            .collect();
+
+        fs::write(&path, serde_json::to_string_pretty(&data)?)?;
+        let json_size = fs::metadata(&path)?.len();
+
+        assert_eq!(PACKED.get(), data);
+        assert!(fs::metadata(&path)?.len() < json_size / 10);
+        assert!(serde_json::from_slice::<Value>(&fs::read(&path)?).is_err());
+        assert_eq!(PACKED.get(), data);
+
+        Ok(())
+    }
+
+    static OBSERVED: OnDisk<i32> = OnDisk::new("observed_i32_test");
+
+    #[test]
+    fn notifications() -> Result<()> {
+        use_test_storage_dir();
+        // Values set on main thread are reported right away and values set on
+        // other threads on the next dispatch. Which thread is main depends on
+        // the other tests so dispatched callbacks are invoked after each set.
+        let received = Arc::new(Mutex::new(vec![]));
+        let owner = Own::new(0);
+
+        let capture = received.clone();
+        OBSERVED.on_change(owner.weak(), move |val| capture.lock().unwrap().push(val));
+
+        OBSERVED.set(1);
+        invoke_dispatched();
+        OBSERVED.set(2);
+        invoke_dispatched();
+        assert_eq!(*received.lock().unwrap(), vec![1, 2]);
+
+        thread::spawn(|| OBSERVED.set(3)).join().unwrap();
+        invoke_dispatched();
+        assert_eq!(*received.lock().unwrap(), vec![1, 2, 3]);
+
+        // Files are checked directly so the test doesn't depend on watcher
+        // timing. Check waits for the watcher thread if it is checking too.
+        watch_external_changes(Duration::from_millis(10));
+        check_external_changes();
+
+        let edited = storage_dir()?.join("observed_i32_test.edited");
+        fs::write(&edited, json!({ "store_version": 0, "value": 55 }).to_string())?;
+        fs::rename(edited, storage_dir()?.join("observed_i32_test"))?;
+        check_external_changes();
+        invoke_dispatched();
+        assert_eq!(*received.lock().unwrap(), vec![1, 2, 3, 55]);
+
+        OBSERVED.set(4);
+        check_external_changes();
+        invoke_dispatched();
+        assert_eq!(*received.lock().unwrap(), vec![1, 2, 3, 55, 4]);
+
+        drop(owner);
+        OBSERVED.set(5);
+        invoke_dispatched();
+        assert_eq!(*received.lock().unwrap(), vec![1, 2, 3, 55, 4]);
+
+        Ok(())
+    }
+
+    static GLOBAL: OnDisk<i32> = OnDisk::new("namespaced_i32_test");
+    static AUDIO: OnDisk<i32> = OnDisk::new("namespaced_i32_test").namespace("audio");
+
+    #[test]
+    fn namespaces() -> Result<()> {
+        use_test_storage_dir();
+        GLOBAL.set(1);
+        AUDIO.set(2);
+
+        assert_eq!(GLOBAL.get(), 1);
+        assert_eq!(AUDIO.get(), 2);
+        assert!(storage_dir()?.join("audio").join("namespaced_i32_test").exists());
+
+        Ok(())
+    }
+
     #[test]
     fn paths() {
+        use_test_storage_dir();
         assert!(executable_name().starts_with("store"));
     }
 }
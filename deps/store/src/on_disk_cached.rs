@@ -0,0 +1,290 @@
+use std::{
+    fmt::{Debug, Formatter},
+    mem,
+    sync::{Condvar, Mutex, MutexGuard, Once},
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::error;
+use refs::Weak;
+
+use crate::{
+    changes::{notify, subscribe, Change},
+    storable::Storable,
+    storage::generation,
+    OnDisk, StoreResult,
+};
+
+/// When cached value is written to disk after `set`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Flush {
+    /// Write when value was not changed for given duration.
+    After(Duration),
+    /// Write only on explicit `flush` or [`flush_all`].
+    OnExit,
+}
+
+struct Cache<T> {
+    value:      Option<T>,
+    dirty:      bool,
+    generation: u64,
+}
+
+/// [`OnDisk`] that loads value once and keeps it in memory.
+/// `set` updates memory immediately and writes to disk later according to
+/// [`Flush`] mode. Meant to be used as `static`.
+pub struct OnDiskCached<T: Storable + Clone> {
+    inner: OnDisk<T>,
+    flush: Flush,
+    cache: Mutex<Cache<T>>,
+}
+
+impl<T: Storable + Clone + Sync> OnDiskCached<T> {
+    pub const fn new(name: &'static str, flush: Flush) -> Self {
+        OnDisk::new(name).cached(flush)
+    }
+
+    pub(crate) const fn with_inner(inner: OnDisk<T>, flush: Flush) -> Self {
+        Self {
+            inner,
+            flush,
+            cache: Mutex::new(Cache {
+                value:      None,
+                dirty:      false,
+                generation: 0,
+            }),
+        }
+    }
+
+    /// Panics on storage errors. See [`OnDisk::get`].
+    pub fn get(&self) -> T {
+        self.lock().value.get_or_insert_with(|| self.inner.get()).clone()
+    }
+
+    pub fn try_get(&self) -> StoreResult<T> {
+        let mut cache = self.lock();
+
+        if let Some(value) = &cache.value {
+            return Ok(value.clone());
+        }
+
+        let value = self.inner.try_get()?;
+        Ok(cache.value.insert(value).clone())
+    }
+
+    pub fn set(&'static self, val: impl Into<T>) {
+        let mut cache = self.lock();
+        cache.value = Some(val.into());
+        cache.dirty = true;
+        let value = cache.value.clone();
+        drop(cache);
+
+        schedule(self, self.flush);
+
+        if let Some(value) = value {
+            notify(self.inner.storage(), &value);
+        }
+    }
+
+    pub fn reset(&'static self) {
+        self.set(T::default());
+    }
+
+    /// Writes cached value to disk if it was changed.
+    pub fn flush(&self) -> StoreResult<()> {
+        let mut cache = self.lock();
+
+        if !cache.dirty {
+            return Ok(());
+        }
+
+        if let Some(value) = &cache.value {
+            self.inner.write(value)?;
+        }
+
+        cache.dirty = false;
+
+        Ok(())
+    }
+
+    /// See [`OnDisk::on_change`]. Externally edited file replaces cached value
+    /// unless it has changes that are not written yet.
+    pub fn on_change<U: ?Sized>(&'static self, subscriber: Weak<U>, action: impl FnMut(T) + Send + 'static) {
+        subscribe(
+            self.inner.storage(),
+            subscriber,
+            |change| {
+                if matches!(change, Change::External) {
+                    self.invalidate();
+                }
+                self.try_get()
+            },
+            action,
+        );
+    }
+
+    fn invalidate(&self) {
+        let mut cache = self.lock();
+        if !cache.dirty {
+            cache.value = None;
+        }
+    }
+
+    /// Value is read again if storage was replaced after it was loaded.
+    /// Unwritten changes are kept since storage is flushed before replacing.
+    fn lock(&self) -> MutexGuard<Cache<T>> {
+        let mut cache = self.cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if cache.generation != generation() {
+            if !cache.dirty {
+                cache.value = None;
+            }
+            cache.generation = generation();
+        }
+
+        cache
+    }
+}
+
+impl<T: Storable + Clone + Sync + Debug> Debug for OnDiskCached<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+trait PendingFlush: Sync {
+    fn flush_pending(&self);
+}
+
+impl<T: Storable + Clone + Sync> PendingFlush for OnDiskCached<T> {
+    fn flush_pending(&self) {
+        if let Err(err) = self.flush() {
+            error!("Failed to flush {}: {err}", self.inner.storage().name);
+        }
+    }
+}
+
+struct Pending {
+    store:    &'static dyn PendingFlush,
+    deadline: Option<Instant>,
+}
+
+static PENDING: Mutex<Vec<Pending>> = Mutex::new(Vec::new());
+static WAKE: Condvar = Condvar::new();
+static FLUSHER: Once = Once::new();
+
+fn pending() -> MutexGuard<'static, Vec<Pending>> {
+    PENDING.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn schedule(store: &'static dyn PendingFlush, flush: Flush) {
+    let deadline = match flush {
+        Flush::After(delay) => Some(Instant::now() + delay),
+        Flush::OnExit => None,
+    };
+
+    let mut pending = pending();
+    pending.retain(|pending| !std::ptr::addr_eq(pending.store, store));
+    pending.push(Pending { store, deadline });
+    drop(pending);
+
+    if deadline.is_some() {
+        FLUSHER.call_once(|| {
+            thread::Builder::new()
+                .name("store flush".into())
+                .spawn(flush_loop)
+                .expect("Failed to spawn store flush thread");
+        });
+        WAKE.notify_one();
+    }
+}
+
+fn flush_loop() {
+    let mut pending = pending();
+
+    loop {
+        let now = Instant::now();
+
+        let (due, rest): (Vec<_>, Vec<_>) = pending
+            .drain(..)
+            .partition(|pending| pending.deadline.is_some_and(|deadline| deadline <= now));
+        *pending = rest;
+
+        if !due.is_empty() {
+            drop(pending);
+            for due in due {
+                due.store.flush_pending();
+            }
+            pending = self::pending();
+            continue;
+        }
+
+        pending = match pending.iter().filter_map(|pending| pending.deadline).min() {
+            Some(deadline) => WAKE.wait_timeout(pending, deadline - now).unwrap().0,
+            None => WAKE.wait(pending).unwrap(),
+        };
+    }
+}
+
+/// Writes all changed cached values to disk. Called by the engine when app
+/// goes to background or exits.
+pub fn flush_all() {
+    for pending in mem::take(&mut *pending()) {
+        pending.store.flush_pending();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{thread::sleep, time::Duration};
+
+    use crate::{on_disk_cached::flush_all, storage::use_test_storage_dir, Flush, OnDisk, OnDiskCached};
+
+    static CACHED: OnDiskCached<i32> = OnDiskCached::new("cached_i32_test", Flush::OnExit);
+    static CACHED_DISK: OnDisk<i32> = OnDisk::new("cached_i32_test");
+
+    static DEBOUNCED: OnDiskCached<String> =
+        OnDisk::new("debounced_string_test").cached(Flush::After(Duration::from_millis(50)));
+    static DEBOUNCED_DISK: OnDisk<String> = OnDisk::new("debounced_string_test");
+
+    #[test]
+    fn cached() {
+        use_test_storage_dir();
+        CACHED.set(1);
+        CACHED.flush().unwrap();
+        assert_eq!(CACHED_DISK.get(), 1);
+
+        CACHED.set(5);
+        assert_eq!(CACHED.get(), 5);
+        assert_eq!(format!("{CACHED:?}"), "5");
+        assert_eq!(CACHED_DISK.get(), 1);
+
+        CACHED_DISK.set(10);
+        assert_eq!(CACHED.get(), 5);
+
+        CACHED.flush().unwrap();
+        assert_eq!(CACHED_DISK.get(), 5);
+
+        CACHED.set(20);
+        flush_all();
+        assert_eq!(CACHED_DISK.get(), 20);
+    }
+
+    #[test]
+    fn debounced() {
+        use_test_storage_dir();
+        DEBOUNCED_DISK.set("initial");
+
+        for i in 0..5 {
+            DEBOUNCED.set(format!("value {i}"));
+        }
+
+        assert_eq!(DEBOUNCED.get(), "value 4");
+        assert_eq!(DEBOUNCED_DISK.get(), "initial");
+
+        sleep(Duration::from_millis(500));
+
+        assert_eq!(DEBOUNCED_DISK.get(), "value 4");
+    }
+}
@@ -1,55 +1,149 @@
-use std::{marker::PhantomData, str::from_utf8};
-
-use serde_json::{from_str, to_string};
+use std::marker::PhantomData;
 
 use crate::{
-    encrypt::{decrypt, encrypt, EncryptionKey},
+    codec::{decode, encode},
+    encrypt::{decrypt_rotated, encrypt, EncryptionKey},
+    migration::split_version,
     storable::Storable,
-    OnDisk,
+    storage::{Loaded, Storage},
+    Codec, Json, Recovery, StoreError, StoreResult,
 };
 
+/// Stores raw encrypted bytes. Value is encoded with selected codec before
+/// encryption.
 pub struct OnDiskEncrypted<T: Storable> {
-    inner: OnDisk<Vec<u8>>,
-    _p:    PhantomData<T>,
+    storage: Storage,
+    codec:   &'static dyn Codec,
+    _p:      PhantomData<T>,
 }
 
 impl<T: Storable> OnDiskEncrypted<T> {
     pub const fn new(name: &'static str) -> Self {
         Self {
-            inner: OnDisk::new(name),
-            _p:    PhantomData,
+            storage: Storage::new(name),
+            codec:   &Json,
+            _p:      PhantomData,
+        }
+    }
+
+    /// See [`OnDisk::namespace`](crate::OnDisk::namespace).
+    pub const fn namespace(self, namespace: &'static str) -> Self {
+        Self {
+            storage: self.storage.namespace(namespace),
+            ..self
+        }
+    }
+
+    /// See [`OnDisk::backups`](crate::OnDisk::backups).
+    pub const fn backups(self, backups: usize) -> Self {
+        Self {
+            storage: self.storage.backups(backups),
+            ..self
         }
     }
 
+    /// See [`OnDisk::codec`](crate::OnDisk::codec).
+    pub const fn codec(self, codec: &'static dyn Codec) -> Self {
+        Self { codec, ..self }
+    }
+
+    /// Panics on storage errors.
     pub fn set(&self, val: impl Into<T>, key: &EncryptionKey) {
-        let val = val.into();
-        let string = to_string(&val).unwrap();
-        let encrypted = encrypt(string.as_bytes(), key);
-        self.inner.set(encrypted);
+        self.try_set(val, key)
+            .unwrap_or_else(|err| panic!("Failed to store {}: {err}", self.storage.name));
     }
 
+    /// Panics on storage and decryption errors.
     pub fn get(&self, key: &EncryptionKey) -> T {
-        let encrypted = self.inner.get();
-        if encrypted.is_empty() {
-            return T::default();
-        }
-        let string = decrypt(encrypted.as_slice(), key);
-        from_str(from_utf8(&string).unwrap()).unwrap()
+        self.try_get(key)
+            .unwrap_or_else(|err| panic!("Failed to get {}: {err}", self.storage.name))
     }
 
     pub fn reset(&self, key: &EncryptionKey) {
         self.set(T::default(), key);
     }
+
+    pub fn try_set(&self, val: impl Into<T>, key: &EncryptionKey) -> StoreResult<()> {
+        self.write(&val.into(), key)
+    }
+
+    pub fn try_get(&self, key: &EncryptionKey) -> StoreResult<T> {
+        match self.storage.read(|data| self.load(data, key))? {
+            Some((val, rewrite)) => {
+                if rewrite {
+                    self.write(&val, key)?;
+                }
+                Ok(val)
+            }
+            None => Ok(T::default()),
+        }
+    }
+
+    pub fn try_reset(&self, key: &EncryptionKey) -> StoreResult<()> {
+        self.try_set(T::default(), key)
+    }
+
+    /// Same as [`OnDisk::get_or_recover`](crate::OnDisk::get_or_recover).
+    /// Data encrypted with a wrong key fails with [`StoreError::Decrypt`].
+    pub fn get_or_recover(
+        &self,
+        key: &EncryptionKey,
+        hook: impl FnOnce(&StoreError) -> Recovery,
+    ) -> StoreResult<T> {
+        match self.try_get(key) {
+            Ok(val) => Ok(val),
+            Err(err) => {
+                self.storage.recover(err, hook)?;
+                self.try_reset(key)?;
+                Ok(T::default())
+            }
+        }
+    }
+
+    fn write(&self, val: &T, key: &EncryptionKey) -> StoreResult<()> {
+        self.storage.write(&encrypt(&encode(val, 0, self.codec)?, key), |data| {
+            self.load(data, key)
+        })
+    }
+
+    /// Encrypted bytes used to be stored as a json array. Data encrypted with
+    /// a previous key is written again with current key.
+    fn load(&self, data: &[u8], key: &EncryptionKey) -> StoreResult<Loaded<T>> {
+        if let Ok((decrypted, rotated)) = decrypt_rotated(data, key) {
+            let (val, rewrite) = decode(&decrypted, self.codec, &[])?;
+            return Ok((val, rewrite || rotated));
+        }
+
+        let legacy: Vec<u8> = Json
+            .decode(data)
+            .and_then(|json| Ok(serde_json::from_value(split_version(json).1)?))
+            .map_err(|_| StoreError::Decrypt)?;
+
+        if legacy.is_empty() {
+            return Ok((T::default(), true));
+        }
+
+        let (val, _) = decode(&decrypt_rotated(&legacy, key)?.0, self.codec, &[])?;
+        Ok((val, true))
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use std::fs;
+
     use anyhow::Result;
     use fake::{Fake, Faker};
     use serde::{Deserialize, Serialize};
     use tokio::spawn;
 
-    use crate::{on_disk_encrypted::OnDiskEncrypted, EncryptionKey};
+    use crate::{
+        add_previous_key,
+        encrypt::decrypt,
+        on_disk_encrypted::OnDiskEncrypted,
+        storage::{storage_dir, use_test_storage_dir},
+        EncryptionKey, MessagePack, Recovery, StoreError,
+    };
 
     #[derive(Debug, PartialEq, Default, Serialize, Deserialize, Clone)]
     struct Data {
@@ -60,16 +154,18 @@ mod test {
     static STORED: OnDiskEncrypted<i32> = OnDiskEncrypted::new("stored_i32_encrypted_test");
     static STORED_STRUCT: OnDiskEncrypted<Data> = OnDiskEncrypted::new("stored_struct_encrypted_test");
 
-    static KEY: EncryptionKey = [
+    static KEY: EncryptionKey = EncryptionKey::legacy([
         1, 2, 3, 4, 5, 6, 7, 8, 9, 8, 7, 6, 5, 4, 3, 2, 1, 2, 5, 5, 5, 5, 5, 5, 5, 4, 3, 2, 1, 2, 3, 4, 5, 6,
         7, 8, 9, 0, 9, 87, 6, 5, 3, 3,
-    ];
+    ]);
 
     fn check_send<T: Send>(_send: &T) {}
     fn check_sync<T: Sync>(_sync: &T) {}
 
     #[tokio::test]
     async fn encrypted_stored() -> Result<()> {
+        use_test_storage_dir();
+
         check_send(&STORED);
         check_sync(&STORED);
         check_send(&STORED_STRUCT);
@@ -106,4 +202,88 @@ mod test {
 
         Ok(())
     }
+
+    static WRONG_KEY_STORED: OnDiskEncrypted<Data> = OnDiskEncrypted::new("wrong_key_encrypted_test");
+
+    static LEGACY_STORED: OnDiskEncrypted<Data> =
+        OnDiskEncrypted::new("legacy_encrypted_test").codec(&MessagePack).backups(0);
+
+    #[test]
+    fn legacy_format() -> Result<()> {
+        use_test_storage_dir();
+        let path = storage_dir()?.join("legacy_encrypted_test");
+        fs::create_dir_all(storage_dir()?)?;
+
+        let data = Data {
+            number: 5,
+            string: "legacy".to_string(),
+        };
+
+        // Written by `OnDiskEncrypted` before random nonces: json array of
+        // ciphertext encrypted with nonce of the legacy key.
+        let fixture = include_bytes!("../fixtures/legacy_encrypted.json");
+        fs::write(&path, fixture)?;
+
+        assert_eq!(LEGACY_STORED.try_get(&KEY)?, data);
+        assert!(fs::metadata(&path)?.len() < fixture.len() as u64 / 2);
+        assert_eq!(LEGACY_STORED.try_get(&KEY)?, data);
+
+        fs::write(&path, "[]")?;
+        assert_eq!(LEGACY_STORED.try_get(&KEY)?, Data::default());
+
+        Ok(())
+    }
+
+    #[test]
+    fn wrong_key() -> Result<()> {
+        use_test_storage_dir();
+        let wrong_key = EncryptionKey::random();
+
+        let data = Data {
+            number: 5,
+            string: "secret".to_string(),
+        };
+
+        WRONG_KEY_STORED.try_set(data.clone(), &KEY)?;
+
+        assert!(matches!(
+            WRONG_KEY_STORED.try_get(&wrong_key),
+            Err(StoreError::Decrypt)
+        ));
+        assert_eq!(WRONG_KEY_STORED.try_get(&KEY)?, data);
+
+        assert_eq!(
+            WRONG_KEY_STORED.get_or_recover(&wrong_key, |_| Recovery::Reset)?,
+            Data::default()
+        );
+        assert_eq!(WRONG_KEY_STORED.try_get(&wrong_key)?, Data::default());
+
+        Ok(())
+    }
+
+    static ROTATED: OnDiskEncrypted<Data> = OnDiskEncrypted::new("rotated_key_encrypted_test");
+
+    #[test]
+    fn key_rotation() -> Result<()> {
+        use_test_storage_dir();
+        let old_key = EncryptionKey::random();
+        let new_key = EncryptionKey::random();
+
+        let data = Data {
+            number: 7,
+            string: "rotated".to_string(),
+        };
+
+        ROTATED.try_set(data.clone(), &old_key)?;
+        assert!(matches!(ROTATED.try_get(&new_key), Err(StoreError::Decrypt)));
+
+        add_previous_key(old_key.clone());
+        assert_eq!(ROTATED.try_get(&new_key)?, data);
+
+        let stored = fs::read(storage_dir()?.join("rotated_key_encrypted_test"))?;
+        assert!(decrypt(&stored, &new_key).is_ok());
+        assert!(decrypt(&stored, &old_key).is_err());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,328 @@
+use std::{collections::BTreeMap, marker::PhantomData};
+
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Deserialize, Serialize,
+};
+
+use crate::{
+    journal::{Index, Journal, Record, Records},
+    storable::Storable,
+    storage::Storage,
+    StoreError, StoreResult,
+};
+
+#[derive(Serialize, Deserialize)]
+enum Entry<K, V> {
+    Insert(K, V),
+    Remove(K),
+    Clear,
+}
+
+struct Keys<K>(BTreeMap<K, Record>);
+
+impl<K> Default for Keys<K> {
+    fn default() -> Self {
+        Self(BTreeMap::new())
+    }
+}
+
+impl<K: DeserializeOwned + Ord + Send + 'static> Index for Keys<K> {
+    fn apply(&mut self, line: &[u8], record: Record) -> StoreResult<()> {
+        match serde_json::from_slice::<Entry<K, IgnoredAny>>(line)? {
+            Entry::Insert(key, _) => _ = self.0.insert(key, record),
+            Entry::Remove(key) => _ = self.0.remove(&key),
+            Entry::Clear => self.0.clear(),
+        }
+        Ok(())
+    }
+
+    fn records_mut(&mut self) -> impl Iterator<Item = &mut Record> {
+        self.0.values_mut()
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Persistent map. Every change appends one record to a journal file so
+/// single entry updates don't rewrite the whole map. Only keys are kept in
+/// memory. Meant to be used as `static`.
+pub struct OnDiskMap<K, V> {
+    journal: Journal<Keys<K>>,
+    _p:      PhantomData<V>,
+}
+
+impl<K, V> OnDiskMap<K, V>
+where
+    K: Serialize + DeserializeOwned + Ord + Clone + Send + 'static,
+    V: Storable,
+{
+    pub const fn new(name: &'static str) -> Self {
+        Self::with_storage(Storage::new(name))
+    }
+
+    /// See [`OnDisk::namespace`](crate::OnDisk::namespace).
+    pub const fn namespaced(namespace: &'static str, name: &'static str) -> Self {
+        Self::with_storage(Storage::new(name).namespace(namespace))
+    }
+
+    const fn with_storage(storage: Storage) -> Self {
+        Self {
+            journal: Journal::new(storage),
+            _p:      PhantomData,
+        }
+    }
+
+    /// Panics on storage errors.
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.try_get(key).unwrap_or_else(|err| self.panic(err))
+    }
+
+    /// Panics on storage errors.
+    pub fn insert(&self, key: K, val: impl Into<V>) {
+        self.try_insert(key, val).unwrap_or_else(|err| self.panic(err));
+    }
+
+    /// Panics on storage errors.
+    pub fn update(&self, key: K, action: impl FnOnce(&mut V)) {
+        self.try_update(key, action).unwrap_or_else(|err| self.panic(err));
+    }
+
+    /// Panics on storage errors. Returns whether the key was present.
+    pub fn remove(&self, key: &K) -> bool {
+        self.try_remove(key).unwrap_or_else(|err| self.panic(err))
+    }
+
+    /// Panics on storage errors.
+    pub fn clear(&self) {
+        self.try_clear().unwrap_or_else(|err| self.panic(err));
+    }
+
+    /// Panics on storage errors.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.journal
+            .with(|keys, _| Ok(keys.0.contains_key(key)))
+            .unwrap_or_else(|err| self.panic(err))
+    }
+
+    /// Panics on storage errors.
+    pub fn len(&self) -> usize {
+        self.journal
+            .with(|keys, _| Ok(keys.len()))
+            .unwrap_or_else(|err| self.panic(err))
+    }
+
+    /// Panics on storage errors.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Panics on storage errors.
+    pub fn keys(&self) -> Vec<K> {
+        self.try_keys().unwrap_or_else(|err| self.panic(err))
+    }
+
+    /// Entries in key order. Values are read one by one while iterating.
+    /// Panics on storage errors.
+    pub fn iter(&self) -> impl Iterator<Item = (K, V)> + '_ {
+        self.try_iter()
+            .unwrap_or_else(|err| self.panic(err))
+            .map(|entry| entry.unwrap_or_else(|err| self.panic(err)))
+    }
+
+    pub fn try_get(&self, key: &K) -> StoreResult<Option<V>> {
+        self.journal
+            .with(|keys, records| keys.0.get(key).map(|record| self.read(records, record)).transpose())
+    }
+
+    pub fn try_insert(&self, key: K, val: impl Into<V>) -> StoreResult<()> {
+        let val = val.into();
+        self.journal.with(|keys, records| {
+            let record = records.append(&Entry::Insert(&key, &val))?;
+            keys.0.insert(key, record);
+            Ok(())
+        })
+    }
+
+    /// Missing value is created with `Default` before update.
+    pub fn try_update(&self, key: K, action: impl FnOnce(&mut V)) -> StoreResult<()> {
+        self.journal.with(|keys, records| {
+            let mut val = match keys.0.get(&key) {
+                Some(record) => self.read(records, record)?,
+                None => V::default(),
+            };
+            action(&mut val);
+            let record = records.append(&Entry::Insert(&key, &val))?;
+            keys.0.insert(key, record);
+            Ok(())
+        })
+    }
+
+    pub fn try_remove(&self, key: &K) -> StoreResult<bool> {
+        self.journal.with(|keys, records| {
+            if keys.0.remove(key).is_none() {
+                return Ok(false);
+            }
+            records.append(&Entry::<&K, ()>::Remove(key))?;
+            Ok(true)
+        })
+    }
+
+    pub fn try_clear(&self) -> StoreResult<()> {
+        self.journal.with(|keys, records| {
+            records.append(&Entry::<(), ()>::Clear)?;
+            keys.0.clear();
+            Ok(())
+        })
+    }
+
+    pub fn try_keys(&self) -> StoreResult<Vec<K>> {
+        self.journal.with(|keys, _| Ok(keys.0.keys().cloned().collect()))
+    }
+
+    /// Entries removed while iterating are skipped.
+    pub fn try_iter(&self) -> StoreResult<impl Iterator<Item = StoreResult<(K, V)>> + '_> {
+        Ok(
+            self.try_keys()?.into_iter().filter_map(|key| match self.try_get(&key) {
+                Ok(val) => val.map(|val| Ok((key, val))),
+                Err(err) => Some(Err(err)),
+            }),
+        )
+    }
+
+    /// Rewrites journal file without overwritten and removed entries. Happens
+    /// automatically when most of the records are obsolete.
+    pub fn compact(&self) -> StoreResult<()> {
+        self.journal.compact()
+    }
+
+    fn read(&self, records: &Records, record: &Record) -> StoreResult<V> {
+        match records.read(record)? {
+            Entry::Insert(_, val) => Ok(val),
+            Entry::<K, V>::Remove(_) | Entry::Clear => Err(StoreError::Parse(
+                format!("Unexpected record in {}", self.journal.name()).into(),
+            )),
+        }
+    }
+
+    fn panic(&self, err: StoreError) -> ! {
+        panic!("Failed to access {}: {err}", self.journal.name())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use anyhow::Result;
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        storage::{storage_dir, use_test_storage_dir},
+        OnDiskMap,
+    };
+
+    #[derive(Debug, PartialEq, Default, Serialize, Deserialize, Clone)]
+    struct Progress {
+        stars:     u32,
+        completed: bool,
+    }
+
+    static PROGRESS: OnDiskMap<u32, Progress> = OnDiskMap::namespaced("map_test", "progress");
+    static PROGRESS_RELOADED: OnDiskMap<u32, Progress> = OnDiskMap::namespaced("map_test", "progress");
+
+    #[test]
+    fn map() -> Result<()> {
+        use_test_storage_dir();
+        PROGRESS.clear();
+        assert!(PROGRESS.is_empty());
+
+        for level in 0..10 {
+            PROGRESS.insert(
+                level,
+                Progress {
+                    stars:     level % 4,
+                    completed: false,
+                },
+            );
+        }
+
+        PROGRESS.update(3, |progress| progress.completed = true);
+        PROGRESS.update(20, |progress| progress.stars = 1);
+        assert!(PROGRESS.remove(&5));
+        assert!(!PROGRESS.remove(&5));
+
+        assert_eq!(PROGRESS.len(), 10);
+        assert!(PROGRESS.contains_key(&20));
+        assert!(!PROGRESS.contains_key(&5));
+        assert_eq!(PROGRESS.get(&5), None);
+        assert_eq!(
+            PROGRESS.get(&3),
+            Some(Progress {
+                stars:     3,
+                completed: true,
+            })
+        );
+
+        let keys: Vec<_> = PROGRESS.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, [0, 1, 2, 3, 4, 6, 7, 8, 9, 20]);
+
+        assert_eq!(PROGRESS_RELOADED.keys(), keys);
+        assert_eq!(PROGRESS_RELOADED.get(&3), PROGRESS.get(&3));
+
+        // Both share one index so records appended and moved by compaction
+        // through one of them are read correctly through the other.
+        PROGRESS_RELOADED.insert(
+            30,
+            Progress {
+                stars:     2,
+                completed: true,
+            },
+        );
+        PROGRESS.compact()?;
+        PROGRESS.update(30, |progress| progress.stars = 3);
+
+        assert_eq!(PROGRESS_RELOADED.get(&30).map(|progress| progress.stars), Some(3));
+        assert_eq!(PROGRESS_RELOADED.get(&3), PROGRESS.get(&3));
+        assert_eq!(PROGRESS_RELOADED.len(), 11);
+
+        Ok(())
+    }
+
+    static SCORES: OnDiskMap<String, u32> = OnDiskMap::new("scores_map_test");
+    static SCORES_RELOADED: OnDiskMap<String, u32> = OnDiskMap::new("scores_map_test");
+
+    #[test]
+    fn compaction() -> Result<()> {
+        use_test_storage_dir();
+        let path = storage_dir()?.join("scores_map_test");
+
+        SCORES.clear();
+
+        for score in 0..1000_u32 {
+            SCORES.insert("player".to_string(), score);
+        }
+        SCORES.insert("other".to_string(), 5_u32);
+
+        assert!(fs::read_to_string(&path)?.lines().count() < 100);
+
+        SCORES.compact()?;
+        assert_eq!(fs::read_to_string(&path)?.lines().count(), 2);
+        assert_eq!(SCORES.get(&"player".to_string()), Some(999));
+
+        let mut data = fs::read(&path)?;
+        data.extend_from_slice(br#"{"Insert":["broken","#);
+        fs::write(&path, data)?;
+
+        assert_eq!(SCORES_RELOADED.len(), 2);
+        assert_eq!(SCORES_RELOADED.get(&"other".to_string()), Some(5));
+        assert_eq!(fs::read_to_string(&path)?.lines().count(), 2);
+
+        SCORES_RELOADED.insert("new".to_string(), 1_u32);
+        assert_eq!(SCORES_RELOADED.get(&"new".to_string()), Some(1));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,312 @@
+use std::marker::PhantomData;
+
+use serde::{de::IgnoredAny, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    journal::{Index, Journal, Record, Records},
+    storable::Storable,
+    storage::Storage,
+    StoreError, StoreResult,
+};
+
+#[derive(Serialize, Deserialize)]
+enum Entry<T> {
+    Push(T),
+    Set(usize, T),
+    Remove(usize),
+    Clear,
+}
+
+#[derive(Default)]
+struct Positions(Vec<Record>);
+
+impl Index for Positions {
+    fn apply(&mut self, line: &[u8], record: Record) -> StoreResult<()> {
+        let entry = serde_json::from_slice::<Entry<IgnoredAny>>(line)?;
+
+        let out_of_bounds = match entry {
+            Entry::Set(index, _) | Entry::Remove(index) => index >= self.0.len(),
+            Entry::Push(_) | Entry::Clear => false,
+        };
+
+        if out_of_bounds {
+            return Err(StoreError::Parse("Record index is out of bounds".into()));
+        }
+
+        match entry {
+            Entry::Push(_) => self.0.push(record),
+            Entry::Set(index, _) => self.0[index] = record,
+            Entry::Remove(index) => _ = self.0.remove(index),
+            Entry::Clear => self.0.clear(),
+        }
+
+        Ok(())
+    }
+
+    fn records_mut(&mut self) -> impl Iterator<Item = &mut Record> {
+        self.0.iter_mut()
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Positions of elements change after removal so all live records become
+    /// pushes in their current order.
+    fn compacted(line: &[u8]) -> StoreResult<Vec<u8>> {
+        match serde_json::from_slice::<Entry<Value>>(line)? {
+            Entry::Push(val) | Entry::Set(_, val) => Ok(serde_json::to_vec(&Entry::Push(val))?),
+            Entry::Remove(_) | Entry::Clear => Err(StoreError::Parse("Unexpected record".into())),
+        }
+    }
+}
+
+/// Persistent list. Same as [`OnDiskMap`](crate::OnDiskMap) but indexed by
+/// position. `push` doesn't rewrite previous elements.
+pub struct OnDiskVec<T> {
+    journal: Journal<Positions>,
+    _p:      PhantomData<T>,
+}
+
+impl<T: Storable> OnDiskVec<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self::with_storage(Storage::new(name))
+    }
+
+    /// See [`OnDisk::namespace`](crate::OnDisk::namespace).
+    pub const fn namespaced(namespace: &'static str, name: &'static str) -> Self {
+        Self::with_storage(Storage::new(name).namespace(namespace))
+    }
+
+    const fn with_storage(storage: Storage) -> Self {
+        Self {
+            journal: Journal::new(storage),
+            _p:      PhantomData,
+        }
+    }
+
+    /// Panics on storage errors.
+    pub fn get(&self, index: usize) -> Option<T> {
+        self.try_get(index).unwrap_or_else(|err| self.panic(err))
+    }
+
+    /// Panics on storage errors.
+    pub fn push(&self, val: impl Into<T>) {
+        self.try_push(val).unwrap_or_else(|err| self.panic(err));
+    }
+
+    /// Panics on storage errors and if `index` is out of bounds.
+    pub fn set(&self, index: usize, val: impl Into<T>) {
+        self.try_set(index, val).unwrap_or_else(|err| self.panic(err));
+    }
+
+    /// Panics on storage errors and if `index` is out of bounds.
+    pub fn update(&self, index: usize, action: impl FnOnce(&mut T)) {
+        self.try_update(index, action).unwrap_or_else(|err| self.panic(err));
+    }
+
+    /// Panics on storage errors and if `index` is out of bounds.
+    pub fn remove(&self, index: usize) -> T {
+        self.try_remove(index).unwrap_or_else(|err| self.panic(err))
+    }
+
+    /// Panics on storage errors.
+    pub fn clear(&self) {
+        self.try_clear().unwrap_or_else(|err| self.panic(err));
+    }
+
+    /// Panics on storage errors.
+    pub fn len(&self) -> usize {
+        self.try_len().unwrap_or_else(|err| self.panic(err))
+    }
+
+    /// Panics on storage errors.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Elements are read one by one while iterating.
+    /// Panics on storage errors.
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        self.try_iter()
+            .unwrap_or_else(|err| self.panic(err))
+            .map(|val| val.unwrap_or_else(|err| self.panic(err)))
+    }
+
+    pub fn try_get(&self, index: usize) -> StoreResult<Option<T>> {
+        self.journal.with(|positions, records| {
+            positions.0.get(index).map(|record| self.read(records, record)).transpose()
+        })
+    }
+
+    pub fn try_push(&self, val: impl Into<T>) -> StoreResult<()> {
+        let val = val.into();
+        self.journal.with(|positions, records| {
+            let record = records.append(&Entry::Push(&val))?;
+            positions.0.push(record);
+            Ok(())
+        })
+    }
+
+    /// Fails with [`StoreError::OutOfBounds`] if `index` is out of bounds.
+    pub fn try_set(&self, index: usize, val: impl Into<T>) -> StoreResult<()> {
+        let val = val.into();
+        self.journal.with(|positions, records| {
+            check_bounds(index, positions)?;
+            positions.0[index] = records.append(&Entry::Set(index, &val))?;
+            Ok(())
+        })
+    }
+
+    /// Fails with [`StoreError::OutOfBounds`] if `index` is out of bounds.
+    pub fn try_update(&self, index: usize, action: impl FnOnce(&mut T)) -> StoreResult<()> {
+        self.journal.with(|positions, records| {
+            check_bounds(index, positions)?;
+            let mut val = self.read(records, &positions.0[index])?;
+            action(&mut val);
+            positions.0[index] = records.append(&Entry::Set(index, &val))?;
+            Ok(())
+        })
+    }
+
+    /// Fails with [`StoreError::OutOfBounds`] if `index` is out of bounds.
+    pub fn try_remove(&self, index: usize) -> StoreResult<T> {
+        self.journal.with(|positions, records| {
+            check_bounds(index, positions)?;
+            let val = self.read(records, &positions.0[index])?;
+            records.append(&Entry::<()>::Remove(index))?;
+            positions.0.remove(index);
+            Ok(val)
+        })
+    }
+
+    pub fn try_clear(&self) -> StoreResult<()> {
+        self.journal.with(|positions, records| {
+            records.append(&Entry::<()>::Clear)?;
+            positions.0.clear();
+            Ok(())
+        })
+    }
+
+    pub fn try_len(&self) -> StoreResult<usize> {
+        self.journal.with(|positions, _| Ok(positions.len()))
+    }
+
+    /// Iteration stops early if elements are removed while iterating.
+    pub fn try_iter(&self) -> StoreResult<impl Iterator<Item = StoreResult<T>> + '_> {
+        Ok((0..self.try_len()?).map_while(|index| self.try_get(index).transpose()))
+    }
+
+    /// See [`OnDiskMap::compact`](crate::OnDiskMap::compact).
+    pub fn compact(&self) -> StoreResult<()> {
+        self.journal.compact()
+    }
+
+    fn read(&self, records: &Records, record: &Record) -> StoreResult<T> {
+        match records.read(record)? {
+            Entry::Push(val) | Entry::Set(_, val) => Ok(val),
+            Entry::Remove(_) | Entry::Clear => Err(StoreError::Parse(
+                format!("Unexpected record in {}", self.journal.name()).into(),
+            )),
+        }
+    }
+
+    fn panic(&self, err: StoreError) -> ! {
+        panic!("Failed to access {}: {err}", self.journal.name())
+    }
+}
+
+fn check_bounds(index: usize, positions: &Positions) -> StoreResult<()> {
+    if index < positions.len() {
+        Ok(())
+    } else {
+        Err(StoreError::OutOfBounds {
+            index,
+            len: positions.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use anyhow::Result;
+
+    use crate::{
+        storage::{storage_dir, use_test_storage_dir},
+        OnDiskVec, StoreError,
+    };
+
+    static REPLAYS: OnDiskVec<String> = OnDiskVec::new("replays_vec_test");
+    static REPLAYS_RELOADED: OnDiskVec<String> = OnDiskVec::new("replays_vec_test");
+
+    #[test]
+    fn vec() -> Result<()> {
+        use_test_storage_dir();
+        REPLAYS.clear();
+
+        for index in 0..5 {
+            REPLAYS.push(format!("replay {index}"));
+        }
+
+        let size = fs::metadata(storage_dir()?.join("replays_vec_test"))?.len();
+        REPLAYS.push("replay 5");
+        assert!(fs::metadata(storage_dir()?.join("replays_vec_test"))?.len() < size + 32);
+
+        REPLAYS.set(1, "first");
+        REPLAYS.update(2, |replay| replay.push_str(" updated"));
+        assert_eq!(REPLAYS.remove(0), "replay 0");
+
+        assert_eq!(REPLAYS.len(), 5);
+        assert_eq!(REPLAYS.get(0).as_deref(), Some("first"));
+        assert_eq!(REPLAYS.get(5), None);
+
+        let expected = ["first", "replay 2 updated", "replay 3", "replay 4", "replay 5"];
+        assert_eq!(REPLAYS.iter().collect::<Vec<_>>(), expected);
+        assert_eq!(REPLAYS_RELOADED.iter().collect::<Vec<_>>(), expected);
+
+        REPLAYS.compact()?;
+        assert_eq!(REPLAYS.iter().collect::<Vec<_>>(), expected);
+
+        let compacted = fs::read_to_string(storage_dir()?.join("replays_vec_test"))?;
+        assert!(compacted.lines().all(|line| line.starts_with(r#"{"Push""#)));
+
+        Ok(())
+    }
+
+    static EMPTY: OnDiskVec<i32> = OnDiskVec::new("out_of_bounds_vec_test");
+
+    #[test]
+    fn out_of_bounds() {
+        use_test_storage_dir();
+        EMPTY.clear();
+        EMPTY.push(1);
+
+        assert!(matches!(
+            EMPTY.try_set(3, 5),
+            Err(StoreError::OutOfBounds { index: 3, len: 1 })
+        ));
+        assert!(matches!(
+            EMPTY.try_update(1, |val| *val += 1),
+            Err(StoreError::OutOfBounds { index: 1, len: 1 })
+        ));
+        assert!(matches!(
+            EMPTY.try_remove(2),
+            Err(StoreError::OutOfBounds { index: 2, len: 1 })
+        ));
+        assert_eq!(EMPTY.iter().collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Failed to access out_of_bounds_panic_vec_test: Index 3 is out of bounds of length 0"
+    )]
+    fn out_of_bounds_panic() {
+        use_test_storage_dir();
+        static EMPTY: OnDiskVec<i32> = OnDiskVec::new("out_of_bounds_panic_vec_test");
+        EMPTY.clear();
+        EMPTY.set(3, 5);
+    }
+}
@@ -0,0 +1,343 @@
+use std::{
+    io,
+    marker::PhantomData,
+    path::PathBuf,
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::backend,
+    codec::{decode, encode},
+    encrypt::{decrypt_rotated, encrypt},
+    migration::current_version,
+    storage::storage_dir,
+    EncryptionKey, Json, Migration, OnDiskMap, Storable, StoreError, StoreResult, Thumbnail,
+};
+
+const DATA_EXTENSION: &str = "save";
+const THUMBNAIL_EXTENSION: &str = "thumb";
+
+/// Description of a save slot. Available without loading slot data or
+/// decryption key.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SlotInfo {
+    pub name:          String,
+    pub saved_at:      DateTime<Utc>,
+    pub play_time:     Duration,
+    pub game_version:  String,
+    pub encrypted:     bool,
+    pub has_thumbnail: bool,
+}
+
+/// Part of [`SaveSlots`] that doesn't depend on saved data type. Used by
+/// views that browse slots.
+pub trait SlotList {
+    /// Most recently saved first.
+    fn slots(&self) -> StoreResult<Vec<SlotInfo>>;
+    fn thumbnail(&self, slot: &str) -> StoreResult<Option<Thumbnail>>;
+    fn delete(&self, slot: &str) -> StoreResult<bool>;
+    fn duplicate(&self, from: &str, to: &str) -> StoreResult<bool>;
+}
+
+/// Named saves of the whole game state with metadata and thumbnails. Each slot
+/// is a separate file in `<name>` directory. Meant to be used as `static`.
+pub struct SaveSlots<T: Storable> {
+    name:         &'static str,
+    slots:        OnDiskMap<String, SlotInfo>,
+    game_version: &'static str,
+    migrations:   &'static [Migration],
+    key:          Mutex<Option<EncryptionKey>>,
+    _p:           PhantomData<T>,
+}
+
+impl<T: Storable> SaveSlots<T> {
+    /// `game_version` is stored in [`SlotInfo`] of every saved slot. Usually
+    /// `env!("CARGO_PKG_VERSION")`.
+    pub const fn new(name: &'static str, game_version: &'static str) -> Self {
+        Self::with_migrations(name, game_version, &[])
+    }
+
+    /// See [`OnDisk::with_migrations`](crate::OnDisk::with_migrations).
+    pub const fn with_migrations(
+        name: &'static str,
+        game_version: &'static str,
+        migrations: &'static [Migration],
+    ) -> Self {
+        Self {
+            name,
+            slots: OnDiskMap::namespaced(name, "slots"),
+            game_version,
+            migrations,
+            key: Mutex::new(None),
+            _p: PhantomData,
+        }
+    }
+
+    /// Slots saved after this call are encrypted the same way as
+    /// [`OnDiskEncrypted`](crate::OnDiskEncrypted). Metadata and thumbnails
+    /// stay readable.
+    pub fn set_key(&self, key: EncryptionKey) {
+        *self.key() = Some(key);
+    }
+
+    /// Panics on storage errors.
+    pub fn list(&self) -> Vec<SlotInfo> {
+        self.slots().unwrap_or_else(|err| self.panic(err))
+    }
+
+    /// Panics on storage errors.
+    pub fn save(&self, slot: &str, val: &T, play_time: Duration, thumbnail: Option<&Thumbnail>) {
+        self.try_save(slot, val, play_time, thumbnail)
+            .unwrap_or_else(|err| self.panic(err));
+    }
+
+    /// Panics on storage and decryption errors.
+    pub fn load(&self, slot: &str) -> Option<T> {
+        self.try_load(slot).unwrap_or_else(|err| self.panic(err))
+    }
+
+    pub fn info(&self, slot: &str) -> StoreResult<Option<SlotInfo>> {
+        self.slots.try_get(&slot.to_string())
+    }
+
+    pub fn try_save(
+        &self,
+        slot: &str,
+        val: &T,
+        play_time: Duration,
+        thumbnail: Option<&Thumbnail>,
+    ) -> StoreResult<()> {
+        let data = encode(val, current_version(self.migrations), &Json)?;
+
+        let key = self.key().clone();
+        let data = match &key {
+            Some(key) => encrypt(&data, key),
+            None => data,
+        };
+
+        backend().write(&self.path(slot, DATA_EXTENSION)?, &data)?;
+
+        let thumbnail_path = self.path(slot, THUMBNAIL_EXTENSION)?;
+        match thumbnail {
+            Some(thumbnail) => {
+                backend().write(&thumbnail_path, &thumbnail.scaled(Thumbnail::MAX_WIDTH).encode()?)?;
+            }
+            None => backend().remove(&thumbnail_path)?,
+        }
+
+        self.slots.try_insert(
+            slot.to_string(),
+            SlotInfo {
+                name: slot.to_string(),
+                saved_at: Utc::now(),
+                play_time,
+                game_version: self.game_version.to_string(),
+                encrypted: key.is_some(),
+                has_thumbnail: thumbnail.is_some(),
+            },
+        )
+    }
+
+    /// Returns `None` if there is no such slot. Encrypted slots fail with
+    /// [`StoreError::Decrypt`] if key is not set or wrong. Slots encrypted
+    /// with a previous key are written again with current key.
+    pub fn try_load(&self, slot: &str) -> StoreResult<Option<T>> {
+        let Some(info) = self.info(slot)? else {
+            return Ok(None);
+        };
+
+        let path = self.path(slot, DATA_EXTENSION)?;
+        let data = backend().read(&path)?;
+
+        let key = self.key().clone();
+        let (data, rotated) = match (info.encrypted, &key) {
+            (false, _) => (data, false),
+            (true, Some(key)) => decrypt_rotated(&data, key)?,
+            (true, None) => return Err(StoreError::Decrypt),
+        };
+
+        let (val, rewrite) = decode(&data, &Json, self.migrations)?;
+
+        if rewrite || rotated {
+            let data = encode(&val, current_version(self.migrations), &Json)?;
+            let data = match (info.encrypted, &key) {
+                (true, Some(key)) => encrypt(&data, key),
+                _ => data,
+            };
+            backend().write(&path, &data)?;
+        }
+
+        Ok(Some(val))
+    }
+
+    fn path(&self, slot: &str, extension: &str) -> StoreResult<PathBuf> {
+        let valid = !slot.is_empty()
+            && !slot.starts_with('.')
+            && !slot.contains(|c: char| matches!(c, '/' | '\\' | ':') || c.is_control());
+
+        if !valid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid save slot name: {slot:?}"),
+            )
+            .into());
+        }
+
+        Ok(storage_dir()?.join(self.name).join(format!("{slot}.{extension}")))
+    }
+
+    fn key(&self) -> MutexGuard<Option<EncryptionKey>> {
+        self.key.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn panic(&self, err: StoreError) -> ! {
+        panic!("Failed to access {} save slots: {err}", self.name)
+    }
+}
+
+impl<T: Storable> SlotList for SaveSlots<T> {
+    fn slots(&self) -> StoreResult<Vec<SlotInfo>> {
+        let mut slots: Vec<_> = self
+            .slots
+            .try_iter()?
+            .map(|slot| slot.map(|(_, info)| info))
+            .collect::<StoreResult<_>>()?;
+        slots.sort_by(|a, b| b.saved_at.cmp(&a.saved_at));
+        Ok(slots)
+    }
+
+    fn thumbnail(&self, slot: &str) -> StoreResult<Option<Thumbnail>> {
+        let path = self.path(slot, THUMBNAIL_EXTENSION)?;
+
+        if !backend().exists(&path) {
+            return Ok(None);
+        }
+
+        Thumbnail::decode(&backend().read(&path)?).map(Some)
+    }
+
+    fn delete(&self, slot: &str) -> StoreResult<bool> {
+        backend().remove(&self.path(slot, DATA_EXTENSION)?)?;
+        backend().remove(&self.path(slot, THUMBNAIL_EXTENSION)?)?;
+        self.slots.try_remove(&slot.to_string())
+    }
+
+    /// Overwrites `to` slot if it exists.
+    fn duplicate(&self, from: &str, to: &str) -> StoreResult<bool> {
+        let Some(info) = self.info(from)? else {
+            return Ok(false);
+        };
+
+        backend().copy(&self.path(from, DATA_EXTENSION)?, &self.path(to, DATA_EXTENSION)?)?;
+
+        let thumbnail = self.path(to, THUMBNAIL_EXTENSION)?;
+        if info.has_thumbnail {
+            backend().copy(&self.path(from, THUMBNAIL_EXTENSION)?, &thumbnail)?;
+        } else {
+            backend().remove(&thumbnail)?;
+        }
+
+        self.slots.try_insert(
+            to.to_string(),
+            SlotInfo {
+                name: to.to_string(),
+                ..info
+            },
+        )?;
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use gm::{flat::Size, U8Color};
+    use serde::{Deserialize, Serialize};
+
+    use crate::{storage::use_test_storage_dir, EncryptionKey, SaveSlots, SlotList, StoreError, Thumbnail};
+
+    #[derive(Debug, PartialEq, Default, Serialize, Deserialize, Clone)]
+    struct Game {
+        level: u32,
+        hero:  String,
+    }
+
+    static SAVES: SaveSlots<Game> = SaveSlots::new("save_slots_test", "1.2.3");
+    static ENCRYPTED: SaveSlots<Game> = SaveSlots::new("encrypted_save_slots_test", "");
+
+    #[test]
+    fn save_slots() -> Result<()> {
+        use_test_storage_dir();
+        for slot in SAVES.list() {
+            SAVES.delete(&slot.name)?;
+        }
+
+        let game = Game {
+            level: 5,
+            hero:  "Sokol".into(),
+        };
+
+        let thumbnail = Thumbnail::new(vec![U8Color::rgba(1, 2, 3, 4); 1000 * 10], Size::new(1000, 10))?;
+
+        SAVES.save("first", &game, Duration::from_secs(60), Some(&thumbnail));
+        SAVES.save("second", &Game::default(), Duration::from_secs(5), None);
+
+        assert_eq!(SAVES.load("first"), Some(game.clone()));
+        assert_eq!(SAVES.load("missing"), None);
+
+        let list = SAVES.list();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].name, "second");
+        assert_eq!(list[1].game_version, "1.2.3");
+        assert_eq!(list[1].play_time, Duration::from_secs(60));
+        assert!(list[1].has_thumbnail);
+
+        let stored = SAVES.thumbnail("first")?.unwrap();
+        assert_eq!(stored.size(), Size::new(Thumbnail::MAX_WIDTH, 3));
+        assert_eq!(stored.data()[0], U8Color::rgba(1, 2, 3, 4));
+        assert_eq!(SAVES.thumbnail("second")?, None);
+
+        assert!(SAVES.duplicate("first", "copy")?);
+        assert!(!SAVES.duplicate("missing", "copy")?);
+        assert_eq!(SAVES.load("copy"), Some(game));
+        assert!(SAVES.thumbnail("copy")?.is_some());
+
+        assert!(SAVES.delete("first")?);
+        assert!(!SAVES.delete("first")?);
+        assert_eq!(SAVES.load("first"), None);
+        assert_eq!(SAVES.list().len(), 2);
+
+        assert!(SAVES.try_save("../escape", &Game::default(), Duration::ZERO, None).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn encrypted_save_slots() -> Result<()> {
+        use_test_storage_dir();
+        let game = Game {
+            level: 10,
+            hero:  "Secret".into(),
+        };
+
+        ENCRYPTED.set_key(EncryptionKey::new([5; 32]));
+        ENCRYPTED.save("slot", &game, Duration::ZERO, None);
+        assert!(ENCRYPTED.info("slot")?.unwrap().encrypted);
+        assert_eq!(ENCRYPTED.load("slot"), Some(game.clone()));
+
+        ENCRYPTED.set_key(EncryptionKey::new([6; 32]));
+        assert!(matches!(ENCRYPTED.try_load("slot"), Err(StoreError::Decrypt)));
+
+        ENCRYPTED.set_key(EncryptionKey::new([5; 32]));
+        assert_eq!(ENCRYPTED.load("slot"), Some(game));
+
+        Ok(())
+    }
+}
@@ -1,5 +1,5 @@
 use serde::{de::DeserializeOwned, Serialize};
 
-pub trait Storable: Serialize + DeserializeOwned + Send + Default {}
+pub trait Storable: Serialize + DeserializeOwned + Send + Default + 'static {}
 
-impl<T: Serialize + DeserializeOwned + Send + Default> Storable for T {}
+impl<T: Serialize + DeserializeOwned + Send + Default + 'static> Storable for T {}
@@ -0,0 +1,336 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+use gm::Platform;
+use log::{error, warn};
+use serde::Deserialize;
+
+use crate::{
+    backend::backend,
+    changes::own_write,
+    file::{backup_file, rotate_backups, with_suffix},
+    Recovery, StoreError, StoreResult,
+};
+
+const DEFAULT_BACKUPS: usize = 0;
+
+/// Environment variable that overrides storage directory.
+pub const STORAGE_DIR_VAR: &str = "TE_STORAGE_DIR";
+
+static STORAGE_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+pub fn executable_name() -> String {
+    try_executable_name().expect("Failed to get executable name")
+}
+
+fn try_executable_name() -> io::Result<String> {
+    let exe = std::env::current_exe()?;
+    let name = exe
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Failed to get executable name"))?;
+    Ok(name.to_string_lossy().into())
+}
+
+fn storage_dir_override() -> MutexGuard<'static, Option<PathBuf>> {
+    STORAGE_DIR.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Directory where all values are stored. Replaces default
+/// `~/.<executable_name>`. [`STORAGE_DIR_VAR`] environment variable takes
+/// precedence.
+pub fn set_storage_dir(dir: impl Into<PathBuf>) {
+    *storage_dir_override() = Some(dir.into());
+}
+
+/// Stores values in `~/.<bundle_id>` so all binaries of a project share them.
+/// On mobile app documents directory is already unique. Values stored before
+/// in default `~/.<executable_name>` are moved to the new directory.
+pub fn set_bundle_id(bundle_id: &str) -> StoreResult<()> {
+    if Platform::MOBILE {
+        return Ok(());
+    }
+    let dir = home_dir()?.join(format!(".{bundle_id}"));
+    migrate_dir(&default_storage_dir()?, &dir);
+    set_storage_dir(dir);
+    Ok(())
+}
+
+/// Moves every stored file missing in new directory. Files present in both
+/// are left in old directory so nothing is overwritten.
+fn migrate_dir(old: &Path, new: &Path) {
+    if old == new {
+        return;
+    }
+
+    let files = match backend().list(old) {
+        Ok(files) => files,
+        Err(err) => {
+            error!("Failed to list stored values in {}: {err}", old.display());
+            return;
+        }
+    };
+
+    for file in files {
+        let Ok(relative) = file.strip_prefix(old) else {
+            continue;
+        };
+        let target = new.join(relative);
+
+        if backend().exists(&target) {
+            continue;
+        }
+
+        match backend().rename(&file, &target) {
+            Ok(()) => warn!(
+                "Moved stored value from {} to {}",
+                file.display(),
+                target.display()
+            ),
+            Err(err) => error!(
+                "Failed to move stored value from {} to {}: {err}",
+                file.display(),
+                target.display()
+            ),
+        }
+    }
+
+    if !backend().list(old).is_ok_and(|left| left.is_empty()) {
+        error!(
+            "Both {} and {} contain stored values. Values left in old directory are not used",
+            old.display(),
+            new.display()
+        );
+    }
+}
+
+/// Takes `bundle_id` from `te.toml` contents.
+/// Usually called with `include_str!("../te.toml")`.
+pub fn use_te_toml(te_toml: &str) -> StoreResult<()> {
+    #[derive(Deserialize)]
+    struct Config {
+        bundle_id: String,
+    }
+
+    let config: Config = toml::from_str(te_toml).map_err(|err| StoreError::Parse(err.into()))?;
+    set_bundle_id(&config.bundle_id)
+}
+
+/// Tests share one temporary storage directory instead of user's home.
+#[cfg(test)]
+pub(crate) fn use_test_storage_dir() {
+    set_storage_dir(std::env::temp_dir().join("store_test"));
+}
+
+fn home_dir() -> StoreResult<PathBuf> {
+    if Platform::MOBILE {
+        dirs::document_dir()
+    } else {
+        dirs::home_dir()
+    }
+    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Failed to get home directory").into())
+}
+
+pub(crate) fn storage_dir() -> StoreResult<PathBuf> {
+    if let Some(dir) = std::env::var_os(STORAGE_DIR_VAR) {
+        return Ok(dir.into());
+    }
+
+    if let Some(dir) = storage_dir_override().clone() {
+        return Ok(dir);
+    }
+
+    default_storage_dir()
+}
+
+fn default_storage_dir() -> StoreResult<PathBuf> {
+    Ok(home_dir()?.join(format!(".{}", try_executable_name()?)))
+}
+
+/// Changes when the whole storage is replaced. Values kept in memory are
+/// dropped when it doesn't match generation they were loaded with.
+pub(crate) fn generation() -> u64 {
+    GENERATION.load(Ordering::Acquire)
+}
+
+pub(crate) fn invalidate_all() {
+    GENERATION.fetch_add(1, Ordering::AcqRel);
+}
+
+/// Loaded value and whether it has to be written back in the current format.
+pub(crate) type Loaded<T> = (T, bool);
+
+pub(crate) type StorageId = (Option<&'static str>, &'static str);
+
+/// File of a single stored value with its backups.
+#[derive(Copy, Clone)]
+pub(crate) struct Storage {
+    pub name:  &'static str,
+    namespace: Option<&'static str>,
+    backups:   usize,
+}
+
+impl Storage {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            namespace: None,
+            backups: DEFAULT_BACKUPS,
+        }
+    }
+
+    pub const fn namespace(self, namespace: &'static str) -> Self {
+        Self {
+            namespace: Some(namespace),
+            ..self
+        }
+    }
+
+    pub const fn backups(self, backups: usize) -> Self {
+        Self { backups, ..self }
+    }
+
+    /// Values with the same name in different namespaces are different values.
+    pub fn id(&self) -> StorageId {
+        (self.namespace, self.name)
+    }
+
+    pub fn path(&self) -> StoreResult<PathBuf> {
+        let dir = storage_dir()?;
+        Ok(match self.namespace {
+            Some(namespace) => dir.join(namespace).join(self.name),
+            None => dir.join(self.name),
+        })
+    }
+
+    /// Previous data becomes the first backup after new data is written. It
+    /// is dropped if `load` doesn't accept it so a corrupted file never
+    /// replaces a good backup.
+    pub fn write<T>(&self, data: &[u8], load: impl Fn(&[u8]) -> StoreResult<Loaded<T>>) -> StoreResult<()> {
+        let path = self.path()?;
+
+        let previous = if self.backups > 0 && backend().exists(&path) {
+            backend().read(&path).ok().filter(|previous| load(previous).is_ok())
+        } else {
+            None
+        };
+
+        own_write(*self, || backend().write(&path, data))?;
+
+        if let Some(previous) = previous {
+            rotate_backups(backend(), &path, &previous, self.backups)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns `None` if nothing is stored yet. If stored file is corrupted
+    /// the most recent backup that `load` accepts replaces it.
+    pub fn read<T>(&self, load: impl Fn(&[u8]) -> StoreResult<Loaded<T>>) -> StoreResult<Option<Loaded<T>>> {
+        let path = self.path()?;
+
+        if !backend().exists(&path) {
+            return Ok(None);
+        }
+
+        let err = match backend().read(&path).map_err(StoreError::from).and_then(|data| load(&data)) {
+            Ok(loaded) => return Ok(Some(loaded)),
+            Err(err @ StoreError::Migration { .. }) => return Err(err),
+            Err(err) => err,
+        };
+
+        for backup in (1..=self.backups).map(|index| backup_file(&path, index)) {
+            let Ok(data) = backend().read(&backup) else {
+                continue;
+            };
+
+            let Ok(loaded) = load(&data) else {
+                continue;
+            };
+
+            warn!(
+                "Failed to get {}: {err}. Restored from backup: {}",
+                self.name,
+                backup.display()
+            );
+            own_write(*self, || backend().write(&path, &data))?;
+            return Ok(Some(loaded));
+        }
+
+        Err(err)
+    }
+
+    /// Returns error back if it should be propagated.
+    pub fn recover(&self, err: StoreError, hook: impl FnOnce(&StoreError) -> Recovery) -> StoreResult<()> {
+        match hook(&err) {
+            Recovery::Propagate => return Err(err),
+            Recovery::Reset => error!("Failed to get {}: {err}. Resetting to default.", self.name),
+            Recovery::Backup => {
+                let backup = self.broken_path(&err)?;
+                error!(
+                    "Failed to get {}: {err}. Resetting to default. Previous data is saved to: {}",
+                    self.name,
+                    backup.display()
+                );
+                backend().rename(&self.path()?, &backup)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Data that failed to migrate is kept with its version in the file name.
+    fn broken_path(&self, err: &StoreError) -> StoreResult<PathBuf> {
+        let suffix = match err {
+            StoreError::Migration { version, .. } => format!(".v{version}"),
+            _ => ".broken".to_string(),
+        };
+        Ok(with_suffix(&self.path()?, &suffix))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use anyhow::Result;
+
+    use crate::storage::migrate_dir;
+
+    #[test]
+    fn dir_migration() -> Result<()> {
+        let root = std::env::temp_dir().join("store_migration_test");
+        _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("old"))?;
+        fs::write(root.join("old").join("value"), "5")?;
+
+        migrate_dir(&root.join("old"), &root.join("new"));
+        assert!(fs::read_dir(root.join("old"))?.next().is_none());
+        assert_eq!(fs::read_to_string(root.join("new").join("value"))?, "5");
+
+        fs::create_dir_all(root.join("old").join("slots"))?;
+        fs::write(root.join("old").join("value"), "10")?;
+        fs::write(root.join("old").join("other"), "20")?;
+        fs::write(root.join("old").join("slots").join("slot"), "30")?;
+
+        migrate_dir(&root.join("old"), &root.join("new"));
+        assert_eq!(fs::read_to_string(root.join("new").join("value"))?, "5");
+        assert_eq!(fs::read_to_string(root.join("new").join("other"))?, "20");
+        assert_eq!(
+            fs::read_to_string(root.join("new").join("slots").join("slot"))?,
+            "30"
+        );
+        assert_eq!(fs::read_to_string(root.join("old").join("value"))?, "10");
+        assert!(!root.join("old").join("other").exists());
+
+        migrate_dir(&root.join("missing"), &root.join("other"));
+        assert!(!root.join("other").exists());
+
+        Ok(())
+    }
+}
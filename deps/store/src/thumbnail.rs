@@ -0,0 +1,134 @@
+use std::io::{Read, Write};
+
+use bytemuck::cast_slice;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use gm::{flat::Size, U8Color};
+
+use crate::{StoreError, StoreResult};
+
+/// Small RGBA picture of a save slot. Usually made from a screenshot.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Thumbnail {
+    data: Vec<U8Color>,
+    size: Size<u32>,
+}
+
+impl Thumbnail {
+    /// Bigger thumbnails are scaled down before saving.
+    pub const MAX_WIDTH: u32 = 320;
+
+    /// Fails if number of pixels doesn't match `size`.
+    pub fn new(data: Vec<U8Color>, size: Size<u32>) -> StoreResult<Self> {
+        if data.len() as u64 != u64::from(size.width) * u64::from(size.height) {
+            return Err(StoreError::Parse(
+                format!("Thumbnail of size {size:?} has {} pixels", data.len()).into(),
+            ));
+        }
+
+        Ok(Self { data, size })
+    }
+
+    pub fn data(&self) -> &[U8Color] {
+        &self.data
+    }
+
+    pub fn size(&self) -> Size<u32> {
+        self.size
+    }
+
+    /// Nearest neighbour downscale to fit `max_width`. Keeps aspect ratio.
+    pub fn scaled(&self, max_width: u32) -> Self {
+        if self.size.width <= max_width || self.size.width == 0 {
+            return self.clone();
+        }
+
+        let width = max_width.max(1);
+        let height = (u64::from(self.size.height) * u64::from(width) / u64::from(self.size.width)).max(1);
+        let height = u32::try_from(height).expect("Scaled height is smaller than original");
+
+        let mut data = Vec::with_capacity(width as usize * height as usize);
+
+        for y in 0..height {
+            let source_y = (u64::from(y) * u64::from(self.size.height) / u64::from(height)) as usize;
+            for x in 0..width {
+                let source_x = (u64::from(x) * u64::from(self.size.width) / u64::from(width)) as usize;
+                data.push(self.data[source_x + source_y * self.size.width as usize]);
+            }
+        }
+
+        Self {
+            data,
+            size: Size::new(width, height),
+        }
+    }
+
+    /// Pixels as RGBA bytes.
+    pub fn bytes(&self) -> &[u8] {
+        cast_slice(&self.data)
+    }
+
+    /// Layout: `width | height | RGBA pixels`, deflate compressed.
+    pub(crate) fn encode(&self) -> StoreResult<Vec<u8>> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&self.size.width.to_le_bytes())?;
+        encoder.write_all(&self.size.height.to_le_bytes())?;
+        encoder.write_all(self.bytes())?;
+        Ok(encoder.finish()?)
+    }
+
+    pub(crate) fn decode(data: &[u8]) -> StoreResult<Self> {
+        let mut decoded = Vec::new();
+        DeflateDecoder::new(data)
+            .read_to_end(&mut decoded)
+            .map_err(|err| StoreError::Parse(err.into()))?;
+
+        let invalid = || StoreError::Parse("Invalid thumbnail data".into());
+
+        let (header, pixels) = decoded.split_at_checked(8).ok_or_else(invalid)?;
+        let width = u32::from_le_bytes(header[..4].try_into().expect("Header has 8 bytes"));
+        let height = u32::from_le_bytes(header[4..].try_into().expect("Header has 8 bytes"));
+
+        if pixels.len() % 4 != 0 {
+            return Err(invalid());
+        }
+
+        let data = pixels
+            .chunks_exact(4)
+            .map(|pixel| U8Color::rgba(pixel[0], pixel[1], pixel[2], pixel[3]))
+            .collect();
+
+        Self::new(data, Size::new(width, height))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use gm::{flat::Size, U8Color};
+
+    use crate::Thumbnail;
+
+    #[test]
+    fn thumbnail() {
+        let data = (0..20_u8)
+            .flat_map(|y| (0..40_u8).map(move |x| U8Color::rgba(x, y, 0, 255)))
+            .collect();
+
+        let thumbnail = Thumbnail::new(data, Size::new(40, 20)).unwrap();
+        let scaled = thumbnail.scaled(10);
+
+        assert_eq!(scaled.size(), Size::new(10, 5));
+        assert_eq!(scaled.data()[0], U8Color::rgba(0, 0, 0, 255));
+        assert_eq!(scaled.data()[1], U8Color::rgba(4, 0, 0, 255));
+        assert_eq!(scaled.data()[10], U8Color::rgba(0, 4, 0, 255));
+        assert_eq!(thumbnail.scaled(100), thumbnail);
+
+        assert_eq!(
+            Thumbnail::decode(&thumbnail.encode().unwrap()).unwrap(),
+            thumbnail
+        );
+        assert!(Thumbnail::decode(b"broken").is_err());
+
+        assert!(Thumbnail::new(vec![U8Color::rgba(0, 0, 0, 255); 10], Size::new(4, 4)).is_err());
+        assert!(Thumbnail::new(vec![], Size::new(0, 4)).is_ok());
+    }
+}
@@ -1,6 +1,6 @@
 use dispatch::{from_main, on_main};
 use gm::{flat::Size, Color};
-use refs::{Own, Weak};
+use refs::{is_main_thread, Own, Weak};
 use vents::OnceEvent;
 
 use crate::{view::ViewSubviews, TouchStack, UIManager, View, ViewData, ViewFrame};
@@ -43,10 +43,15 @@ pub trait ModalView<In = (), Out: 'static = ()>: 'static + View + Default {
     where
         In: 'static + Send,
         Out: Send, {
-        from_main(|| Self::prepare_modally_with_input(input).modal_event().val_async())
-            .await
-            .await
-            .unwrap()
+        // Main thread can't wait for `from_main` but can show modal directly
+        // when called from `spawn_local` future.
+        let result = if is_main_thread() {
+            Self::prepare_modally_with_input(input).modal_event().val_async()
+        } else {
+            from_main(|| Self::prepare_modally_with_input(input).modal_event().val_async()).await
+        };
+
+        result.await.unwrap()
     }
 
     fn hide_modal(mut self: Weak<Self>, result: Out)
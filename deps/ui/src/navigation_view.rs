@@ -1,5 +1,5 @@
 use dispatch::on_main;
-use gm::{Animation, Color};
+use gm::{Animation, Color, Easing};
 use refs::{Own, Weak};
 use ui_proc::view;
 
@@ -57,7 +57,9 @@ impl NavigationView {
             view.set_navigation_view(self);
             view.set_frame(self.frame().with_zero_origin());
 
-            let anim = UIAnimation::new(Animation::new(self.width(), 0.0, 0.5), |view, x| {
+            let animation = Animation::new(self.width(), 0.0, 0.5).easing(Easing::CubicOut);
+
+            let anim = UIAnimation::new(animation, |view, x| {
                 view.set_x(x);
             });
 
@@ -79,7 +81,9 @@ impl NavigationView {
         below.set_hidden(false);
         let mut to_pop = self.subviews.last().unwrap().weak_view();
 
-        let anim = UIAnimation::new(Animation::new(0.0, self.width(), 0.5), |view, x| {
+        let animation = Animation::new(0.0, self.width(), 0.5).easing(Easing::CubicIn);
+
+        let anim = UIAnimation::new(animation, |view, x| {
             view.set_x(x);
         });
 
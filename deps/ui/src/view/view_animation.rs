@@ -1,17 +1,19 @@
 use std::ops::DerefMut;
 
 use educe::Educe;
-use gm::Animation;
+use gm::{Animation, Lerp};
 use vents::OnceEvent;
 
 use crate::{view::view_data::ViewData, View};
 
-type Action = Box<dyn FnMut(&mut dyn View, f32)>;
+/// Sets current value to the view and returns `true` when animation has
+/// finished.
+type Action = Box<dyn FnMut(&mut dyn View) -> bool>;
 
 #[derive(Educe)]
 #[educe(Debug)]
 pub struct UIAnimation {
-    animation:     Animation,
+    finished:      bool,
     #[educe(Debug(ignore))]
     action:        Action,
     #[educe(Debug(ignore))]
@@ -19,20 +21,28 @@ pub struct UIAnimation {
 }
 
 impl UIAnimation {
-    pub fn new(animation: Animation, action: impl FnMut(&mut dyn View, f32) + 'static) -> Self {
+    pub fn new<T: Lerp + 'static>(
+        animation: Animation<T>,
+        mut action: impl FnMut(&mut dyn View, T) + 'static,
+    ) -> Self {
         Self {
-            animation,
-            action: Box::new(action),
+            finished:  false,
+            action:    Box::new(move |view| {
+                // Checked before the value so the last frame sets the end value.
+                let finished = animation.finished();
+                action(view, animation.value());
+                finished
+            }),
             on_finish: OnceEvent::default(),
         }
     }
 
     pub(crate) fn finished(&self) -> bool {
-        self.animation.finished()
+        self.finished
     }
 
     pub(crate) fn commit(&mut self, view: &mut dyn View) {
-        (self.action)(view, self.animation.value());
+        self.finished = (self.action)(view);
     }
 }
 
@@ -1,4 +1,4 @@
-use gm::Animation;
+use gm::{Animation, Easing};
 use refs::Own;
 
 use crate::{
@@ -41,7 +41,7 @@ impl<T: ?Sized + View + 'static> ViewController for T {
             let mut view = UIManager::root_view_weak().__add_subview_internal(view, true);
             view.set_frame(self.frame().with_zero_origin());
             let anim = UIAnimation::new(
-                Animation::new(self.height(), 0.0, PRESENT_ANIMATION_DURATION),
+                Animation::new(self.height(), 0.0, PRESENT_ANIMATION_DURATION).easing(Easing::CubicOut),
                 |view, y| {
                     view.set_y(y);
                 },
@@ -2,6 +2,7 @@ mod button;
 mod circle_view;
 mod image_view;
 mod label;
+mod progress_view;
 mod scroll_view;
 mod slider;
 mod switch;
@@ -12,6 +13,7 @@ pub use button::Button;
 pub use circle_view::CircleView;
 pub use image_view::ImageView;
 pub use label::*;
+pub use progress_view::ProgressView;
 pub use scroll_view::*;
 pub use slider::*;
 pub use switch::*;
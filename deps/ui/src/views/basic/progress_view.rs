@@ -0,0 +1,65 @@
+use dispatch::Job;
+use gm::{Color, ToF32};
+use refs::Weak;
+use ui_proc::view;
+
+use crate::{
+    view::{ViewData, ViewFrame},
+    Container, ViewCallbacks, ViewSetup,
+};
+
+mod test_engine {
+    pub(crate) use educe;
+    pub(crate) use refs;
+
+    pub(crate) use crate as ui;
+}
+
+/// Horizontal bar filled from left to right.
+#[view]
+pub struct ProgressView {
+    progress: f32,
+    job:      Option<Job>,
+
+    #[init]
+    fill: Container,
+}
+
+impl ProgressView {
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    /// `0.0..=1.0`. Values out of range are clamped.
+    pub fn set_progress(&mut self, progress: impl ToF32) -> &mut Self {
+        self.progress = progress.to_f32().clamp(0.0, 1.0);
+        self
+    }
+
+    /// Shows progress of `job` until it finishes.
+    pub fn track(&mut self, job: &Job) -> &mut Self {
+        self.job = Some(job.clone());
+        self
+    }
+}
+
+impl ViewSetup for ProgressView {
+    fn setup(mut self: Weak<Self>) {
+        self.set_color(Color::GRAY);
+        self.fill.set_color(Color::LIGHT_BLUE);
+    }
+}
+
+impl ViewCallbacks for ProgressView {
+    fn update(&mut self) {
+        if let Some(job) = &self.job {
+            self.progress = job.progress();
+            if job.is_finished() {
+                self.job = None;
+            }
+        }
+
+        let frame = (0, 0, self.width() * self.progress, self.height());
+        self.fill.set_frame(frame);
+    }
+}
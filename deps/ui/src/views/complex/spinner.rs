@@ -1,15 +1,16 @@
 use std::{
     f32::consts::PI,
+    mem,
     sync::{Mutex, MutexGuard},
 };
 
-use gm::{Animation, LossyConvert};
+use gm::{Animation, Clock, LossyConvert};
 use ui_proc::view;
 use vents::OnceEvent;
 
 use crate::{
     view::{View, ViewAnimation, ViewData, ViewFrame, ViewSubviews},
-    Container, ModalView, TouchStack, UIAnimation, ViewCallbacks, ViewSetup, MICROSECONDS_IN_ONE_SECOND,
+    Container, ModalView, ProgressView, TouchStack, UIAnimation, ViewCallbacks, ViewSetup,
 };
 
 mod test_engine {
@@ -19,8 +20,7 @@ mod test_engine {
     pub(crate) use crate as ui;
 }
 
-use chrono::Utc;
-use dispatch::{on_main, on_main_sync};
+use dispatch::{on_main, on_main_sync, Job};
 use gm::{
     flat::{point_on_circle, Size},
     Color,
@@ -56,6 +56,10 @@ impl Drop for SpinnerLock {
 pub struct Spinner {
     circles: Vec<Weak<Container>>,
     event:   OnceEvent,
+    job:     Option<Job>,
+
+    #[init]
+    progress: ProgressView,
 }
 
 impl Spinner {
@@ -86,15 +90,20 @@ impl ViewSetup for Spinner {
 
             self.circles.push(circle);
         }
+
+        self.progress.place().lrb(20).h(8);
+        self.progress.set_hidden(true);
     }
 }
 
 impl ViewCallbacks for Spinner {
     fn update(&mut self) {
-        let current_time: i64 = Utc::now().timestamp_micros();
+        if self.job.as_ref().is_some_and(Job::is_finished) {
+            self.job = None;
+            Self::stop();
+        }
 
-        let val = ((current_time % MICROSECONDS_IN_ONE_SECOND).lossy_convert())
-            / MICROSECONDS_IN_ONE_SECOND.lossy_convert();
+        let val = Clock::elapsed().as_secs_f32().fract();
 
         let span = PI * 2.0;
         let start = -PI;
@@ -120,6 +129,26 @@ impl Spinner {
         SpinnerLock { stopped: false }
     }
 
+    /// Shows spinner with progress of `job` and stops it when job finishes
+    /// or is cancelled. Spinner that is already shown tracks the job too.
+    pub fn for_job(job: &Job) {
+        Self::start();
+
+        let job = job.clone();
+        on_main(move || {
+            let mut spinner = Self::current();
+
+            if spinner.is_null() {
+                warn!("Spinner was stopped before job was attached");
+                return;
+            }
+
+            spinner.progress.set_hidden(false);
+            spinner.progress.track(&job);
+            spinner.job = Some(job);
+        });
+    }
+
     pub fn start() {
         trace!("Start spinner");
 
@@ -133,16 +162,19 @@ impl Spinner {
         });
     }
 
+    /// Spinner stops being current right away so spinner started while this
+    /// one fades out is a new one.
     pub fn stop() {
         trace!("Stop spinner");
 
-        if Self::current().is_null() {
+        let mut spinner = mem::take(&mut *Self::current());
+
+        if spinner.is_null() {
             warn!("Spinner already stopped");
             return;
         }
 
-        on_main(|| {
-            let mut spinner = Self::current();
+        on_main(move || {
             TouchStack::pop_layer(spinner.weak_view());
 
             let animation = UIAnimation::new(Animation::new(0.8, 0.0, 0.4), |sp, val| {
@@ -154,10 +186,8 @@ impl Spinner {
                 }
             });
 
-            animation.on_finish.sub(|| {
-                let mut spinner = Self::current();
+            animation.on_finish.sub(move || {
                 spinner.remove_from_superview();
-                *spinner = Weak::default();
             });
 
             spinner.add_animation(animation);
@@ -1,5 +1,6 @@
 use std::sync::Mutex;
 
+use gm::Clock;
 use web_time::Instant;
 
 static LAST_UPDATE: Mutex<Option<Instant>> = Mutex::new(None);
@@ -11,17 +12,17 @@ impl Every {
         let mut lock = LAST_UPDATE.lock().unwrap();
 
         let last_update = lock.unwrap_or_else(|| {
-            let now = Instant::now();
+            let now = Clock::now();
             *lock = now.into();
             now
         });
 
-        if last_update.elapsed().as_secs_f32() < 1.0 {
+        if Clock::now().duration_since(last_update).as_secs_f32() < 1.0 {
             return;
         }
 
         action();
 
-        *lock = Instant::now().into();
+        *lock = Clock::now().into();
     }
 }
@@ -19,4 +19,10 @@ pub trait App {
     fn key_event(&mut self, event: KeyEvent);
     fn set_wgpu_app(&mut self, app: Rglica<WGPUApp>);
     fn dropped_file(&mut self, path: PathBuf);
+    /// App went to background. Mobile systems may kill it without further
+    /// notice.
+    fn suspended(&mut self);
+    /// Event loop is exiting. Not every platform returns from
+    /// [`WGPUApp::start`] after that.
+    fn exiting(&mut self);
 }
@@ -1,3 +1,4 @@
+use gm::Clock;
 use web_time::Instant;
 
 pub(crate) struct FrameCounter {
@@ -12,8 +13,8 @@ pub(crate) struct FrameCounter {
 impl Default for FrameCounter {
     fn default() -> Self {
         Self {
-            last_frame_update: Instant::now(),
-            last_fps_update:   Instant::now(),
+            last_frame_update: Clock::now(),
+            last_fps_update:   Clock::now(),
 
             fps:         0.0,
             frame_time:  0.0,
@@ -23,12 +24,17 @@ impl Default for FrameCounter {
 }
 
 impl FrameCounter {
+    /// Frame time follows [`Clock`] so it is fixed when clock is manual.
     pub fn update(&mut self) -> bool {
+        Clock::next_frame();
+
         self.frame_count += 1;
-        let now = Instant::now();
+        let now = Clock::now();
 
         self.frame_time = (now - self.last_frame_update).as_secs_f32();
-        self.fps = 1.0 / self.frame_time;
+        if self.frame_time > 0.0 {
+            self.fps = 1.0 / self.frame_time;
+        }
         self.last_frame_update = now;
 
         let passed = (now - self.last_fps_update).as_secs_f32();
@@ -303,6 +303,14 @@ impl ApplicationHandler<Events> for WGPUApp {
         };
     }
 
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        self.state.app.suspended();
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        self.state.app.exiting();
+    }
+
     fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
         if self.resumed {
             Self::window().request_redraw();
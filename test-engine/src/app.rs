@@ -1,12 +1,13 @@
 use std::{any::type_name, io::Write, path::PathBuf, ptr::null_mut, time::Duration};
 
 use anyhow::Result;
-use dispatch::{from_main, invoke_dispatched};
+use dispatch::{detect_main_thread_stalls, from_main, invoke_dispatched};
 use env_logger::Builder;
 use gm::flat::{Point, Size};
 use level::LevelBase;
 use log::{Level, LevelFilter};
 use refs::{Own, Rglica};
+use store::Thumbnail;
 use tokio::time::sleep;
 use ui::{Touch, TouchEvent, UIEvents, UIManager, View, ViewData, ViewFrame, ViewSubviews};
 use vents::OnceEvent;
@@ -116,6 +117,9 @@ impl App {
     fn new(first_view: Own<dyn View>) -> Box<Self> {
         Self::setup_log();
 
+        #[cfg(debug_assertions)]
+        detect_main_thread_stalls(Duration::from_secs(5));
+
         #[cfg(desktop)]
         Assets::init(crate::git_root().expect("git_root()"));
         #[cfg(mobile)]
@@ -197,6 +201,12 @@ impl App {
         Ok(screenshot)
     }
 
+    /// Screenshot scaled down to be stored with a save slot.
+    pub async fn take_thumbnail() -> Result<Thumbnail> {
+        let screenshot = Self::take_screenshot().await?;
+        Ok(Thumbnail::new(screenshot.data, screenshot.size)?.scaled(Thumbnail::MAX_WIDTH))
+    }
+
     pub fn fps() -> f32 {
         Self::current().wgpu_app.fps()
     }
@@ -286,4 +296,13 @@ impl wgpu_wrapper::App for App {
         dbg!(type_name::<LevelBase>());
         UIManager::trigger_drop_file(path);
     }
+
+    /// Cached values are written before the system can kill the app.
+    fn suspended(&mut self) {
+        ::store::flush_all();
+    }
+
+    fn exiting(&mut self) {
+        ::store::flush_all();
+    }
 }
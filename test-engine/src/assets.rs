@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use audio::Sound;
+use audio::{Music, Sound};
 use manage::data_manager::DataManager;
 use refs::assert_main_thread;
 use wgpu_wrapper::image::Image;
@@ -17,5 +17,6 @@ impl Assets {
 
         Image::set_root_path(&paths.images);
         Sound::set_root_path(&paths.sounds);
+        Music::set_root_path(&paths.sounds);
     }
 }
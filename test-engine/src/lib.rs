@@ -37,13 +37,18 @@ pub mod gm {
         flat::{Direction, Shape},
         sign::Sign,
         volume::GyroData,
-        Animation, Apply, LossyConvert, Platform, ToF32,
+        Animation, AnimationMode, Apply, Clock, Easing, Keyframes, Lerp, LossyConvert, Platform, ToF32,
     };
 }
 
 pub mod store {
     pub(crate) use store;
-    pub use store::{EncryptionKey, OnDisk, OnDiskEncrypted};
+    pub use store::{
+        add_previous_key, flush_all, set_bundle_id, set_storage_dir, use_memory_storage, use_te_toml,
+        watch_external_changes, Archive, ArchiveEntry, Codec, Compressed, EncryptionKey, Flush, Json,
+        MessagePack, Migration, OnDisk, OnDiskCached, OnDiskEncrypted, OnDiskMap, OnDiskVec, Recovery,
+        SaveSlots, SlotInfo, SlotList, StoreError, StoreResult, Thumbnail, STORAGE_DIR_VAR,
+    };
 }
 
 pub mod time {
@@ -52,7 +57,7 @@ pub mod time {
 
 pub use app::App;
 pub use audio;
-pub use dispatch::{after, async_after, from_main, on_main, wait_for_next_frame};
+pub use dispatch::{after, async_after, from_main, on_main, wait_for_next_frame, Job, JobProgress};
 pub use gen;
 pub use manage::data_manager::DataManager;
 pub use paths::*;
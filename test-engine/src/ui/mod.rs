@@ -11,5 +11,5 @@ pub use gm::{
 pub use input::*;
 pub use ui::UI;
 pub use ui_proc::view;
-pub use views::color_meter::ColorMeter;
+pub use views::{color_meter::ColorMeter, save_slots_view::SaveSlotsView};
 pub use wgpu_wrapper::{image::Image, PolygonMode, Screenshot};
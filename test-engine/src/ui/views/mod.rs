@@ -1 +1,2 @@
 pub(crate) mod color_meter;
+pub(crate) mod save_slots_view;
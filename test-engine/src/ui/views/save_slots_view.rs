@@ -0,0 +1,151 @@
+use std::{any::Any, collections::HashMap, ops::Deref};
+
+use chrono::Local;
+use gm::flat::Size;
+use log::error;
+use refs::{Own, Weak};
+use store::{SlotInfo, SlotList};
+use ui::{Anchor, CollectionData, CollectionView, ImageView, Label, View, ViewData, ViewFrame, ViewSetup};
+use ui_proc::view;
+use vents::Event;
+use wgpu_wrapper::image::Image;
+
+use crate as test_engine;
+
+const CELL_HEIGHT: f32 = 80.0;
+
+#[view]
+struct SaveSlotCell {
+    #[init]
+    image:   ImageView,
+    name:    Label,
+    details: Label,
+}
+
+impl ViewSetup for SaveSlotCell {
+    fn setup(self: Weak<Self>) {
+        self.image.place().tlb(0).relative(Anchor::Width, self, 0.4);
+        self.name
+            .place()
+            .tr(0)
+            .relative(Anchor::Width, self, 0.6)
+            .relative(Anchor::Height, self, 0.5);
+        self.details
+            .place()
+            .br(0)
+            .relative(Anchor::Width, self, 0.6)
+            .relative(Anchor::Height, self, 0.5);
+    }
+}
+
+/// List of save slots with thumbnails, save dates and play time.
+#[view]
+pub struct SaveSlotsView {
+    #[educe(Debug(ignore))]
+    source:   Option<&'static dyn SlotList>,
+    slots:    Vec<SlotInfo>,
+    selected: Event<String>,
+
+    /// Thumbnail images by slot name with save time they were made for. Cells
+    /// are set up again on every layout so images are not recreated there.
+    #[educe(Debug(ignore))]
+    thumbnails: HashMap<String, (i64, Option<Weak<Image>>)>,
+
+    #[init]
+    collection: CollectionView,
+}
+
+impl SaveSlotsView {
+    pub fn set_slots(&mut self, slots: &'static dyn SlotList) {
+        self.source = Some(slots);
+        self.reload();
+    }
+
+    /// Called with name of tapped slot.
+    pub fn on_selected(&self, action: impl FnMut(String) + 'static) {
+        self.selected.val(action);
+    }
+
+    /// Reads slot list again. Call after saving or deleting slots.
+    pub fn reload(&mut self) {
+        let Some(source) = self.source else {
+            return;
+        };
+
+        self.slots = source.slots().unwrap_or_else(|err| {
+            error!("Failed to read save slots: {err}");
+            vec![]
+        });
+
+        let mut thumbnails = HashMap::with_capacity(self.slots.len());
+
+        for info in &self.slots {
+            let saved_at = info.saved_at.timestamp_millis();
+            let image = match self.thumbnails.remove(&info.name) {
+                Some((stamp, image)) if stamp == saved_at => image,
+                _ => Self::thumbnail(source, info),
+            };
+            thumbnails.insert(info.name.clone(), (saved_at, image));
+        }
+
+        self.thumbnails = thumbnails;
+        self.collection.reload_data();
+    }
+
+    fn thumbnail(source: &dyn SlotList, info: &SlotInfo) -> Option<Weak<Image>> {
+        if !info.has_thumbnail {
+            return None;
+        }
+
+        let thumbnail = source.thumbnail(&info.name).ok()??;
+
+        // Resaved slot gets a new image name so a stale texture is not reused.
+        let name = format!("Save slot: {} {}", info.name, info.saved_at.timestamp_millis());
+
+        Image::from_raw_data(thumbnail.bytes(), name, thumbnail.size(), 4).ok()
+    }
+}
+
+impl ViewSetup for SaveSlotsView {
+    fn setup(self: Weak<Self>) {
+        self.collection.place().back();
+        self.collection.set_data_source(self.deref());
+    }
+}
+
+impl CollectionData for SaveSlotsView {
+    fn number_of_cells(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn make_cell(&self) -> Own<dyn View> {
+        SaveSlotCell::new()
+    }
+
+    fn setup_cell_for_index(&self, cell: &mut dyn Any, index: usize) {
+        let cell = cell.downcast_mut::<SaveSlotCell>().unwrap();
+        let info = &self.slots[index];
+
+        if let Some((_, Some(image))) = self.thumbnails.get(&info.name) {
+            cell.image.set_image(*image);
+        }
+
+        let minutes = info.play_time.as_secs() / 60;
+
+        cell.name.set_text(&info.name);
+        cell.details.set_text(format!(
+            "{} {}:{:02}",
+            info.saved_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            minutes / 60,
+            minutes % 60
+        ));
+    }
+
+    fn size_for_index(&self, _index: usize) -> Size {
+        (self.width(), CELL_HEIGHT).into()
+    }
+
+    fn cell_selected(&mut self, index: usize) {
+        self.selected.trigger(self.slots[index].name.clone());
+    }
+}
@@ -47,7 +47,7 @@ impl ViewSetup for PolygonView {
 
         self.center.set_color(Color::WHITE).place().size(5, 5).center();
 
-        after(0.1, move || self.add_first_points());
+        after(0.1, move || self.add_first_points()).owned_by(self);
     }
 }
 
@@ -1,5 +1,6 @@
+use std::{thread::sleep, time::Duration};
+
 use test_engine::{
-    async_after,
     audio::Sound,
     gm::{Apply, Direction, LossyConvert},
     level::{Control, LevelManager},
@@ -11,7 +12,7 @@ use test_engine::{
         Point, PointsPath, PositionView, Spinner, StickView, TextField, UIManager, ViewData, ViewFrame,
         ViewSetup,
     },
-    App, DataManager,
+    App, DataManager, Job,
 };
 use ui_benchmark::BenchmarkView;
 
@@ -133,10 +134,16 @@ impl ViewSetup for TestGameView {
         self.spinner.set_text("Spinner");
         self.spinner.set_text_size(20);
         self.spinner.on_tap(|| {
-            Spinner::start();
-            async_after(4, async {
-                Spinner::stop();
-            });
+            let job = Job::spawn(
+                |progress| {
+                    for step in 1..=40u8 {
+                        sleep(Duration::from_millis(100));
+                        progress.set(f32::from(step) / 40.0);
+                    }
+                },
+                |()| {},
+            );
+            Spinner::for_job(&job);
         });
 
         self.alert.place().size(100, 28).anchor(Anchor::Left, self.scale, 10).anchor(
@@ -158,7 +165,9 @@ impl ViewSetup for TestGameView {
             .anchor(Anchor::Bot, self.alert, 10);
         self.sound.set_text("Sound");
         self.sound.set_text_size(20);
-        self.sound.on_tap(|| Sound::get("retro.wav").play());
+        self.sound.on_tap(|| {
+            Sound::get("retro.wav").play();
+        });
 
         self.color_meter.place().size(100, 100).b(10).anchor(Anchor::Right, self.br, 10);
 
@@ -1,5 +1,5 @@
 use test_engine::{
-    gm::{Animation, Shape},
+    gm::{Animation, AnimationMode, Shape},
     level::{level, LevelCreation, LevelSetup, Player, Sprite, SpriteTemplates, Wall},
     refs::Weak,
     ui::{Alert, Color, Image, UIManager},
@@ -50,10 +50,10 @@ impl BenchmarkLevel {
         self.bottom_moving = self.make_sprite(Shape::rect(5, 14), (0, -68));
         self.bottom_moving.set_image(square);
 
-        self.left_animation = Animation::new(-80.0, -20.0, 2.0);
-        self.right_animation = Animation::new(80.0, 20.0, 2.0);
-        self.floor_animation = Animation::new(-25.0, 0.0, 0.5);
-        self.bottom_animation = Animation::new(-100.0, 100.0, 4.0);
+        self.left_animation = Animation::new(-80.0, -20.0, 2.0).mode(AnimationMode::PingPong);
+        self.right_animation = Animation::new(80.0, 20.0, 2.0).mode(AnimationMode::PingPong);
+        self.floor_animation = Animation::new(-25.0, 0.0, 0.5).mode(AnimationMode::PingPong);
+        self.bottom_animation = Animation::new(-100.0, 100.0, 4.0).mode(AnimationMode::PingPong);
 
         self.make_sprite::<Wall>(Shape::rect(200, 2), (0, -85)).set_image(square);
         self.make_sprite::<Wall>(Shape::rect(2, 200), (120, 0)).set_image(square);
@@ -113,12 +113,9 @@ impl LevelSetup for TestLevel {
         player.weapon.set_image("ak.png");
 
         player.on_collision.sub(move || {
-            LevelManager::level_weak()
-                .as_any_mut()
-                .downcast_mut::<Self>()
-                .unwrap()
-                .collision_sound
-                .play();
+            let level = LevelManager::level_weak();
+            let level = level.as_any().downcast_ref::<Self>().unwrap();
+            player.play_sound(&level.collision_sound);
         });
 
         self.collision_sound = Sound::get("pek.wav");
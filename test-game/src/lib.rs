@@ -6,13 +6,22 @@
 mod interface;
 mod levels;
 
+use log::error;
+
 use crate::interface::test_game_view::TestGameView;
 
+fn use_te_toml() {
+    if let Err(err) = test_engine::store::use_te_toml(include_str!("../../te.toml")) {
+        error!("Failed to set storage directory from te.toml: {err}");
+    }
+}
+
 #[cfg(not(target_os = "android"))]
 #[no_mangle]
 pub extern "C" fn start_test_game() -> std::ffi::c_int {
     use test_engine::ui::ViewSetup;
     let runtime = tokio::runtime::Runtime::new().unwrap();
+    use_te_toml();
     runtime.block_on(async {
         #[cfg(mobile)]
         test_engine::refs::set_current_thread_as_main();
@@ -25,6 +34,7 @@ pub extern "C" fn start_test_game() -> std::ffi::c_int {
 pub fn start_test_game(app: test_engine::AndroidApp) {
     use test_engine::ui::ViewSetup;
     dbg!("HELLOOOddO");
+    use_te_toml();
     let runtime = tokio::runtime::Runtime::new().unwrap();
     runtime.block_on(async {
         test_engine::refs::set_current_thread_as_main();
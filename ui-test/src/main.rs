@@ -45,6 +45,9 @@ mod views;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    test_engine::store::use_memory_storage();
+    test_engine::audio::NullOutput::enable();
+
     App::start_with_actor(Container::new(), async {
         test_engine::ui::UIManager::set_display_touches(true);
 